@@ -0,0 +1,99 @@
+//! The limited number of arrows the player gets in each level.
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::PhysicsSet;
+
+use crate::balloons::BalloonKind;
+use crate::level::{check_level_complete, Balloon, CurrentLevel, Levels};
+use crate::physics::{
+    despawn_finished_arrows, fire_shots, Arrow, BalloonPopEvent, CollisionFlushSet, CollisionSet,
+    Pierce, ShotFinishedEvent, ShotFiredEvent,
+};
+use crate::{GameState, GameplaySet};
+
+pub struct AmmoPlugin;
+
+impl Plugin for AmmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ammo>()
+            .add_systems(
+                PreUpdate,
+                refill_ammo.run_if(resource_exists_and_changed::<CurrentLevel>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Before rapier applies the commands of the new arrows, which this adds to
+                    use_ammo
+                        .after(fire_shots)
+                        .before(PhysicsSet::SyncBackendFlush)
+                        .before(CollisionSet),
+                    grant_bonus_arrows.after(CollisionFlushSet),
+                    check_out_of_ammo
+                        .after(use_ammo)
+                        .after(grant_bonus_arrows)
+                        .after(despawn_finished_arrows)
+                        .after(check_level_complete)
+                        .in_set(GameplaySet),
+                ),
+            );
+    }
+}
+
+/// The arrows left to shoot in the current level.
+#[derive(Resource, Default)]
+pub struct Ammo {
+    pub arrows: u32,
+    /// The arrows shot so far in the current level.
+    pub used: u32,
+    /// How many balloons each arrow can pop, or `None` for no limit.
+    pub pierce: Option<u32>,
+}
+
+fn refill_ammo(mut ammo: ResMut<Ammo>, current_level: Res<CurrentLevel>, levels: Res<Levels>) {
+    let level = &levels.0[current_level.index];
+    ammo.arrows = level.arrows;
+    ammo.used = 0;
+    ammo.pierce = level.pierce;
+}
+
+fn use_ammo(
+    mut commands: Commands,
+    mut ammo: ResMut<Ammo>,
+    mut fired_events: EventReader<ShotFiredEvent>,
+) {
+    for event in fired_events.iter() {
+        ammo.arrows = ammo.arrows.saturating_sub(1);
+        ammo.used += 1;
+        if let Some(pierce) = ammo.pierce {
+            commands.entity(event.arrow).insert(Pierce(pierce));
+        }
+    }
+}
+
+fn grant_bonus_arrows(mut ammo: ResMut<Ammo>, mut pop_events: EventReader<BalloonPopEvent>) {
+    for event in pop_events.iter() {
+        if let BalloonKind::Bonus { arrows } = event.kind {
+            ammo.arrows += arrows;
+        }
+    }
+}
+
+// The game is over once the last arrow is done without popping all balloons
+fn check_out_of_ammo(
+    ammo: Res<Ammo>,
+    mut finished_events: EventReader<ShotFinishedEvent>,
+    arrows: Query<Entity, With<Arrow>>,
+    balloons: Query<&BalloonKind, With<Balloon>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let finished: Vec<Entity> = finished_events.iter().map(|event| event.arrow).collect();
+    if finished.is_empty() {
+        return;
+    }
+
+    let arrows_in_flight = arrows.iter().any(|arrow| !finished.contains(&arrow));
+    if ammo.arrows == 0 && !arrows_in_flight && balloons.iter().any(BalloonKind::must_pop) {
+        next_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,211 @@
+//! The walls surrounding the playing field, and obstacles inside it.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::Collider;
+
+// These values are defined in `Transform` units.
+// Using the default 2D camera they correspond 1:1 with screen pixels.
+
+const WALL_THICKNESS: f32 = 10.0;
+
+const WALL_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const OBSTACLE_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+
+/// The bounds of the playing field, i.e. where the walls are.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Arena {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl Arena {
+    pub fn contains(&self, point: Vec2) -> bool {
+        (self.left..=self.right).contains(&point.x) && (self.bottom..=self.top).contains(&point.y)
+    }
+}
+
+/// What happens to arrows hitting a wall or an obstacle.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum WallBehaviour {
+    /// The arrow stops and stays in the wall.
+    #[default]
+    Stick,
+    /// The arrow bounces off, keeping `restitution` of its speed towards the wall.
+    Bounce { restitution: f32 },
+    /// The arrow is removed.
+    Destroy,
+}
+
+/// The behaviour of each of the four walls.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Walls {
+    #[serde(default)]
+    pub left: WallBehaviour,
+    #[serde(default)]
+    pub right: WallBehaviour,
+    #[serde(default)]
+    pub bottom: WallBehaviour,
+    #[serde(default)]
+    pub top: WallBehaviour,
+}
+
+impl Walls {
+    pub fn behaviour(&self, location: &WallLocation) -> WallBehaviour {
+        match location {
+            WallLocation::Left => self.left,
+            WallLocation::Right => self.right,
+            WallLocation::Bottom => self.bottom,
+            WallLocation::Top => self.top,
+        }
+    }
+}
+
+/// A solid block inside the arena.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Obstacle {
+    pub position: Vec2,
+    pub size: Vec2,
+    #[serde(default)]
+    pub behaviour: WallBehaviour,
+    /// The counterclockwise rotation in radians, for example to make a ramp.
+    ///
+    /// Only the rapier physics backend takes rotation into account.
+    #[serde(default)]
+    pub angle: f32,
+    /// How fast the obstacle rotates, in radians per second. Requires the rapier physics backend.
+    #[serde(default)]
+    pub spin: f32,
+}
+
+/// Blocks hanging below each other from a fixed point, which swing when hit.
+///
+/// Only the rapier physics backend lets the chain move. With the builtin one it hangs straight down.
+/// Arrows sticking in a link push the chain instead of staying in it.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Chain {
+    /// Where the top of the first link is attached, for example just below the top wall.
+    pub anchor: Vec2,
+    pub links: u32,
+    /// The width and height of each link.
+    pub link_size: Vec2,
+    #[serde(default)]
+    pub behaviour: WallBehaviour,
+}
+
+impl Chain {
+    /// Where the center of the link at `index` is while the chain hangs straight down.
+    pub fn link_position(&self, index: u32) -> Vec2 {
+        self.anchor - Vec2::new(0.0, (index as f32 + 0.5) * self.link_size.y)
+    }
+}
+
+// This bundle is a collection of the components that define a "wall" in our game
+#[derive(Bundle)]
+pub struct WallBundle {
+    // You can nest bundles inside of other bundles like this
+    // Allowing you to compose their functionality
+    sprite_bundle: SpriteBundle,
+    collider: Collider,
+    behaviour: WallBehaviour,
+}
+
+/// Which side of the arena is this wall located on?
+pub enum WallLocation {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl WallLocation {
+    fn position(&self, arena: &Arena) -> Vec2 {
+        let center = Vec2::new(arena.left + arena.right, arena.bottom + arena.top) / 2.0;
+
+        match self {
+            WallLocation::Left => Vec2::new(arena.left, center.y),
+            WallLocation::Right => Vec2::new(arena.right, center.y),
+            WallLocation::Bottom => Vec2::new(center.x, arena.bottom),
+            WallLocation::Top => Vec2::new(center.x, arena.top),
+        }
+    }
+
+    fn size(&self, arena: &Arena) -> Vec2 {
+        let arena_height = arena.top - arena.bottom;
+        let arena_width = arena.right - arena.left;
+        // Levels with an impossible arena are rejected when they are read, see `Level::validate`
+        assert!(arena_height > 0.0);
+        assert!(arena_width > 0.0);
+
+        match self {
+            WallLocation::Left | WallLocation::Right => {
+                Vec2::new(WALL_THICKNESS, arena_height + WALL_THICKNESS)
+            }
+            WallLocation::Bottom | WallLocation::Top => {
+                Vec2::new(arena_width + WALL_THICKNESS, WALL_THICKNESS)
+            }
+        }
+    }
+}
+
+impl WallBundle {
+    // This "builder method" allows us to reuse logic across our wall entities,
+    // making our code easier to read and less prone to bugs when we change the logic
+    pub fn new(location: WallLocation, arena: &Arena, walls: &Walls) -> WallBundle {
+        WallBundle::block(
+            location.position(arena),
+            location.size(arena),
+            walls.behaviour(&location),
+            WALL_COLOR,
+        )
+    }
+
+    /// The width and height of the wall.
+    pub fn size(&self) -> Vec2 {
+        self.sprite_bundle.transform.scale.truncate()
+    }
+
+    pub fn obstacle(obstacle: &Obstacle) -> WallBundle {
+        let mut bundle = WallBundle::block(
+            obstacle.position,
+            obstacle.size,
+            obstacle.behaviour,
+            OBSTACLE_COLOR,
+        );
+        bundle.sprite_bundle.transform.rotation = Quat::from_rotation_z(obstacle.angle);
+        bundle
+    }
+
+    pub fn chain_link(chain: &Chain, index: u32) -> WallBundle {
+        WallBundle::block(
+            chain.link_position(index),
+            chain.link_size,
+            chain.behaviour,
+            OBSTACLE_COLOR,
+        )
+    }
+
+    fn block(position: Vec2, size: Vec2, behaviour: WallBehaviour, color: Color) -> WallBundle {
+        WallBundle {
+            sprite_bundle: SpriteBundle {
+                transform: Transform {
+                    // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
+                    // This is used to determine the order of our sprites
+                    translation: position.extend(0.0),
+                    // The z-scale of 2D objects must always be 1.0,
+                    // or their ordering will be affected in surprising ways.
+                    // See https://github.com/bevyengine/bevy/issues/4149
+                    scale: size.extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite { color, ..default() },
+                ..default()
+            },
+            collider: Collider,
+            behaviour,
+        }
+    }
+}
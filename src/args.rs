@@ -0,0 +1,25 @@
+//! Options given on the command line.
+
+/// The value of the option `--<name> <value>` or `--<name>=<value>`, if given.
+///
+/// Always `None` on the web, where there is no command line.
+pub fn option(name: &str) -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let flag = format!("--{name}");
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == flag {
+                return args.next();
+            }
+            if let Some(value) = arg.strip_prefix(&flag).and_then(|v| v.strip_prefix('=')) {
+                return Some(value.to_string());
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let _ = name;
+
+    None
+}
@@ -0,0 +1,276 @@
+//! Sound effects, music and the volume settings.
+
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+};
+use bevy_prng::ChaCha8Rng;
+use rand_core::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::balloons::BalloonKind;
+use crate::physics::{BalloonPopEvent, CollisionSet};
+use crate::{storage, Seed};
+
+/// The key for muting and unmuting all sounds.
+const MUTE_KEY: KeyCode = KeyCode::N;
+/// How much a volume level changes with each key press.
+const VOLUME_STEP: f32 = 0.1;
+/// The keys for turning each volume level down and up.
+const VOLUME_KEYS: [(KeyCode, VolumeLevel, f32); 6] = [
+    (KeyCode::Minus, VolumeLevel::Master, -VOLUME_STEP),
+    (KeyCode::Equals, VolumeLevel::Master, VOLUME_STEP),
+    (KeyCode::BracketLeft, VolumeLevel::Sfx, -VOLUME_STEP),
+    (KeyCode::BracketRight, VolumeLevel::Sfx, VOLUME_STEP),
+    (KeyCode::Comma, VolumeLevel::Music, -VOLUME_STEP),
+    (KeyCode::Period, VolumeLevel::Music, VOLUME_STEP),
+];
+/// The key the audio settings are stored under between sessions.
+const SETTINGS_KEY: &str = "audio";
+
+/// The most pop sounds that can play at the same time. Pops beyond this are silent.
+const MAX_POP_VOICES: usize = 8;
+/// How much the speed, and thereby the pitch, of each pop sound varies in either direction.
+const POP_PITCH_VARIATION: f32 = 0.1;
+/// How much quieter than normal a pop sound can be.
+const POP_VOLUME_VARIATION: f32 = 0.2;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<AudioSettings>(SETTINGS_KEY).unwrap_or_default())
+            .add_systems(
+                Startup,
+                (
+                    load_sounds,
+                    seed_sound_rng,
+                    start_music.after(load_sounds),
+                    save_default_settings,
+                ),
+            )
+            .add_systems(FixedUpdate, play_collision_sound.after(CollisionSet))
+            .add_systems(
+                Update,
+                (
+                    toggle_mute.run_if(input_just_pressed(MUTE_KEY)),
+                    change_volume,
+                    apply_audio_settings
+                        .after(toggle_mute)
+                        .after(change_volume)
+                        .run_if(resource_changed::<AudioSettings>()),
+                ),
+            );
+    }
+}
+
+/// The volume levels, from 0 to 1, which are saved between sessions.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct AudioSettings {
+    /// The volume of everything.
+    pub master: f32,
+    /// The volume of sound effects, on top of the master volume.
+    pub sfx: f32,
+    /// The volume of the music, on top of the master volume.
+    pub music: f32,
+    /// Silences everything without changing the volume levels.
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.0,
+            sfx: 1.0,
+            music: 0.5,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn sfx_volume(&self) -> f32 {
+        self.volume(self.sfx)
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.volume(self.music)
+    }
+
+    fn level_mut(&mut self, level: VolumeLevel) -> &mut f32 {
+        match level {
+            VolumeLevel::Master => &mut self.master,
+            VolumeLevel::Sfx => &mut self.sfx,
+            VolumeLevel::Music => &mut self.music,
+        }
+    }
+
+    fn volume(&self, level: f32) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master.clamp(0.0, 1.0) * level.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// One of the volume levels of [`AudioSettings`].
+#[derive(Clone, Copy)]
+enum VolumeLevel {
+    Master,
+    Sfx,
+    Music,
+}
+
+#[derive(Resource)]
+pub struct Sounds {
+    pub balloon_pop: Handle<AudioSource>,
+    pub music: Handle<AudioSource>,
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let balloon_pop_sound = asset_server.load("sounds/balloon_pop.ogg");
+    let music = asset_server.load("sounds/music.wav");
+    commands.insert_resource(Sounds {
+        balloon_pop: balloon_pop_sound,
+        music,
+    });
+}
+
+/// The background music, which plays in a loop for as long as the game runs.
+#[derive(Component)]
+struct Music;
+
+fn start_music(mut commands: Commands, sounds: Res<Sounds>, settings: Res<AudioSettings>) {
+    commands.spawn((
+        AudioBundle {
+            source: sounds.music.clone(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new_relative(settings.music_volume()),
+                ..default()
+            },
+        },
+        Music,
+    ));
+}
+
+fn toggle_mute(mut settings: ResMut<AudioSettings>) {
+    settings.muted = !settings.muted;
+}
+
+fn change_volume(keyboard_input: Res<Input<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    for (key, level, change) in VOLUME_KEYS {
+        if keyboard_input.just_pressed(key) {
+            let volume = settings.level_mut(level);
+            // Round to whole steps, so the saved settings stay readable
+            *volume = ((*volume + change) / VOLUME_STEP).round() * VOLUME_STEP;
+            *volume = volume.clamp(0.0, 1.0);
+        }
+    }
+}
+
+// Save the default settings the first time, so there is a file to edit
+fn save_default_settings(settings: Res<AudioSettings>) {
+    if storage::load::<AudioSettings>(SETTINGS_KEY).is_none() {
+        storage::save(SETTINGS_KEY, &*settings);
+    }
+}
+
+// Change the volume of the music that is already playing, and remember the settings
+fn apply_audio_settings(settings: Res<AudioSettings>, music: Query<&AudioSink, With<Music>>) {
+    for sink in &music {
+        sink.set_volume(settings.music_volume());
+    }
+    if !settings.is_added() {
+        storage::save(SETTINGS_KEY, &*settings);
+    }
+}
+
+/// The random number generator for varying the sounds.
+///
+/// It is seeded from the game's [`Seed`] but kept apart from the global one, so that sounds
+/// don't change what happens in the game.
+#[derive(Resource)]
+struct SoundRng(ChaCha8Rng);
+
+impl SoundRng {
+    /// A random number between 0 and 1.
+    fn next_f32(&mut self) -> f32 {
+        self.0.next_u32() as f32 / u32::MAX as f32
+    }
+}
+
+fn seed_sound_rng(mut commands: Commands, seed: Res<Seed>) {
+    commands.insert_resource(SoundRng(ChaCha8Rng::seed_from_u64(seed.0)));
+}
+
+/// A playing pop sound.
+#[derive(Component)]
+struct PopSound;
+
+// Each kind of balloon sounds different. They share the same sound, played at a different pitch
+fn play_collision_sound(
+    mut commands: Commands,
+    mut pop_events: EventReader<BalloonPopEvent>,
+    sounds: Res<Sounds>,
+    settings: Res<AudioSettings>,
+    mut rng: ResMut<SoundRng>,
+    arena: Option<Res<Arena>>,
+    playing: Query<(), With<PopSound>>,
+) {
+    let mut voices = playing.iter().count();
+    for event in pop_events.iter() {
+        if voices >= MAX_POP_VOICES {
+            // Keep reading so the skipped pops are not played later
+            continue;
+        }
+        voices += 1;
+
+        let (speed, volume) = pop_sound(&event.kind);
+        let speed = speed * (1.0 + (rng.next_f32() * 2.0 - 1.0) * POP_PITCH_VARIATION);
+        let volume = volume * (1.0 - rng.next_f32() * POP_VOLUME_VARIATION) * settings.sfx_volume();
+
+        // From -1 at the left wall to 1 at the right wall
+        let pan = arena.as_ref().map_or(0.0, |arena| {
+            let center = (arena.left + arena.right) / 2.0;
+            let half_width = (arena.right - arena.left) / 2.0;
+            ((event.position.x - center) / half_width).clamp(-1.0, 1.0)
+        });
+
+        commands.spawn((
+            SpatialAudioBundle {
+                source: sounds.balloon_pop.clone(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    speed,
+                    volume: Volume::new_relative(volume),
+                    ..default()
+                },
+                spatial: pan_settings(pan),
+            },
+            PopSound,
+        ));
+    }
+}
+
+/// Spatial settings which make a sound come from the left (-1), the middle (0) or the right (1).
+fn pan_settings(pan: f32) -> SpatialSettings {
+    // The ears are one unit apart, so the sound is never far enough away to get quieter.
+    // Rodio makes the ear furthest from the sound the loudest, so the sound is mirrored.
+    SpatialSettings::new(Transform::IDENTITY, 1.0, Vec3::new(-pan / 2.0, 0.0, 0.0))
+}
+
+/// The speed and volume of the pop sound for each kind of balloon.
+fn pop_sound(kind: &BalloonKind) -> (f32, f32) {
+    match kind {
+        BalloonKind::Normal => (1.0, 1.0),
+        BalloonKind::Layered { .. } => (1.2, 0.8),
+        BalloonKind::Metal => (0.6, 1.0),
+        BalloonKind::Explosive { .. } => (0.5, 1.5),
+        BalloonKind::Bonus { .. } => (1.5, 1.0),
+    }
+}
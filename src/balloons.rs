@@ -0,0 +1,146 @@
+//! The different kinds of balloons, and what happens when arrows hit them.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::level::Balloon;
+use crate::physics::{check_for_collisions, BalloonHitEvent, BalloonPopEvent, CollisionSet};
+use crate::rapier;
+
+/// How much of its speed an arrow keeps when bouncing off a metal balloon.
+pub const METAL_RESTITUTION: f32 = 0.8;
+
+pub struct BalloonPlugin;
+
+impl Plugin for BalloonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            pop_balloons
+                .after(check_for_collisions)
+                .after(rapier::handle_collisions)
+                .in_set(CollisionSet),
+        );
+    }
+}
+
+/// What kind of balloon a [`Balloon`] is.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum BalloonKind {
+    #[default]
+    Normal,
+    /// Loses one layer per hit, and pops when the last one is gone.
+    Layered { layers: u32 },
+    /// Arrows bounce off it. Only explosions can pop it, and it doesn't have to be popped
+    /// to complete the level.
+    Metal,
+    /// Pops all balloons within `radius` when popped.
+    Explosive { radius: f32 },
+    /// Gives the player more arrows when popped.
+    Bonus { arrows: u32 },
+}
+
+impl BalloonKind {
+    /// The points for popping the whole balloon.
+    pub fn points(&self) -> u32 {
+        match *self {
+            BalloonKind::Normal => 1,
+            BalloonKind::Layered { layers } => layers,
+            BalloonKind::Metal => 5,
+            BalloonKind::Explosive { .. } => 2,
+            BalloonKind::Bonus { .. } => 3,
+        }
+    }
+
+    /// Whether the balloon has to be popped to complete the level.
+    pub fn must_pop(&self) -> bool {
+        *self != BalloonKind::Metal
+    }
+
+    /// The color of the balloon, which the white balloon texture is tinted with.
+    pub fn color(&self) -> Color {
+        match *self {
+            BalloonKind::Normal | BalloonKind::Layered { layers: 0..=1 } => {
+                Color::rgb(0.36, 0.43, 0.88)
+            }
+            BalloonKind::Layered { layers: 2 } => Color::rgb(0.3, 0.75, 0.3),
+            BalloonKind::Layered { layers: 3 } => Color::rgb(0.95, 0.85, 0.2),
+            BalloonKind::Layered { .. } => Color::rgb(1.0, 0.45, 0.7),
+            BalloonKind::Metal => Color::rgb(0.6, 0.62, 0.65),
+            BalloonKind::Explosive { .. } => Color::rgb(0.15, 0.15, 0.15),
+            BalloonKind::Bonus { .. } => Color::rgb(1.0, 0.65, 0.1),
+        }
+    }
+}
+
+fn pop_balloons(
+    mut commands: Commands,
+    mut hit_events: EventReader<BalloonHitEvent>,
+    mut balloons: Query<(Entity, &Transform, &mut BalloonKind, &mut Sprite), With<Balloon>>,
+    mut pop_events: EventWriter<BalloonPopEvent>,
+) {
+    let mut popped = Vec::new();
+    let mut explosions = Vec::new();
+
+    for event in hit_events.iter() {
+        let Ok((balloon, transform, mut kind, mut sprite)) = balloons.get_mut(event.balloon) else {
+            continue;
+        };
+        if popped.contains(&balloon) {
+            continue;
+        }
+
+        match *kind {
+            BalloonKind::Layered { layers } if layers > 1 => {
+                // Only the outer layer pops
+                pop_events.send(BalloonPopEvent {
+                    balloon,
+                    arrow: event.arrow,
+                    position: transform.translation.truncate(),
+                    kind: *kind,
+                    points: 1,
+                });
+                *kind = BalloonKind::Layered { layers: layers - 1 };
+                sprite.color = kind.color();
+                continue;
+            }
+            BalloonKind::Explosive { radius } => {
+                explosions.push((transform.translation.truncate(), radius, event.arrow));
+            }
+            _ => {}
+        }
+
+        pop_events.send(BalloonPopEvent {
+            balloon,
+            arrow: event.arrow,
+            position: transform.translation.truncate(),
+            kind: *kind,
+            points: kind.points(),
+        });
+        commands.entity(balloon).despawn();
+        popped.push(balloon);
+    }
+
+    // Explosions pop every balloon in range completely, and can set off other explosive balloons
+    while let Some((center, radius, arrow)) = explosions.pop() {
+        for (balloon, transform, kind, _) in &balloons {
+            let position = transform.translation.truncate();
+            if popped.contains(&balloon) || position.distance(center) > radius {
+                continue;
+            }
+            if let BalloonKind::Explosive { radius } = *kind {
+                explosions.push((position, radius, arrow));
+            }
+
+            pop_events.send(BalloonPopEvent {
+                balloon,
+                arrow,
+                position,
+                kind: *kind,
+                points: kind.points(),
+            });
+            commands.entity(balloon).despawn();
+            popped.push(balloon);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Visual effects for popping balloons: a burst of particles and the points that were scored.
+//!
+//! The effects are short-lived entities that remove themselves once they have faded out.
+//! They are purely visual, so they don't use the seeded RNG that the gameplay depends on.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::level::LevelEntity;
+use crate::physics::{BalloonPopEvent, CollisionSet};
+use crate::scoring::{count_pops, PointsScoredEvent};
+use crate::GameState;
+
+const PARTICLE_COUNT: usize = 12;
+const PARTICLE_SIZE: Vec2 = Vec2::new(5.0, 5.0);
+/// The speeds of the particles, which alternate to make the burst look less regular.
+const PARTICLE_SPEEDS: [f32; 3] = [120.0, 180.0, 240.0];
+const PARTICLE_LIFETIME: f32 = 0.5;
+const PARTICLE_GRAVITY: f32 = 300.0;
+
+const LABEL_FONT_SIZE: f32 = 24.0;
+const LABEL_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const LABEL_SPEED: f32 = 60.0;
+const LABEL_LIFETIME: f32 = 0.8;
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_pop_effects.after(CollisionSet),
+                spawn_score_labels.after(count_pops),
+            ),
+        )
+        .add_systems(
+            Update,
+            animate_effects.run_if(not(in_state(GameState::Paused))),
+        );
+    }
+}
+
+/// A particle or label that moves and fades out, and is despawned when its timer finishes.
+#[derive(Component)]
+struct Effect {
+    velocity: Vec2,
+    gravity: f32,
+    timer: Timer,
+}
+
+fn spawn_pop_effects(mut commands: Commands, mut pop_events: EventReader<BalloonPopEvent>) {
+    for event in pop_events.iter() {
+        let color = event.kind.color();
+
+        // Spread the particles evenly in all directions
+        for i in 0..PARTICLE_COUNT {
+            let angle = i as f32 / PARTICLE_COUNT as f32 * TAU;
+            let speed = PARTICLE_SPEEDS[i % PARTICLE_SPEEDS.len()];
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(PARTICLE_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(1.0)),
+                    ..default()
+                },
+                Effect {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    gravity: PARTICLE_GRAVITY,
+                    timer: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+                },
+                LevelEntity,
+            ));
+        }
+    }
+}
+
+fn spawn_score_labels(mut commands: Commands, mut scored_events: EventReader<PointsScoredEvent>) {
+    for event in scored_events.iter() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("+{}", event.points),
+                    TextStyle {
+                        font_size: LABEL_FONT_SIZE,
+                        color: LABEL_COLOR,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(event.position.extend(2.0)),
+                ..default()
+            },
+            Effect {
+                velocity: Vec2::new(0.0, LABEL_SPEED),
+                gravity: 0.0,
+                timer: Timer::from_seconds(LABEL_LIFETIME, TimerMode::Once),
+            },
+            LevelEntity,
+        ));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn animate_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut Effect,
+        &mut Transform,
+        Option<&mut Sprite>,
+        Option<&mut Text>,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut effect, mut transform, sprite, text) in &mut query {
+        if effect.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        effect.velocity.y -= effect.gravity * delta;
+        transform.translation += (effect.velocity * delta).extend(0.0);
+
+        let alpha = effect.timer.percent_left();
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_a(alpha);
+        }
+        if let Some(mut text) = text {
+            for section in &mut text.sections {
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Running the game without a window, e.g. on CI machines or in tests.
+//!
+//! ```no_run
+//! use bloons::{headless, scoring::Scoreboard};
+//!
+//! let mut app = headless::headless_app();
+//! headless::run_ticks(&mut app, 600);
+//! println!("score: {}", app.world.resource::<Scoreboard>().score);
+//! ```
+
+use bevy::prelude::*;
+
+use crate::physics::SimulationTick;
+use crate::BloonsPlugin;
+
+/// Creates an `App` containing the gameplay simulation and nothing that needs a window or a GPU.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, BloonsPlugin::headless()));
+    app
+}
+
+/// Updates the app until `ticks` more fixed timesteps have been simulated.
+pub fn run_ticks(app: &mut App, ticks: u64) {
+    let target = app.world.resource::<SimulationTick>().0 + ticks;
+    while app.world.resource::<SimulationTick>().0 < target {
+        app.update();
+    }
+}
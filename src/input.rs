@@ -0,0 +1,297 @@
+//! Shooting arrows with the mouse, and moving between menus, levels and the pause screen.
+
+use bevy::{
+    ecs::system::SystemParam, input::common_conditions::input_just_pressed, prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::ammo::Ammo;
+use crate::arena::Arena;
+use crate::level::{start_game, start_game_at, CurrentLevel, Levels, Power, RetryLevelEvent};
+use crate::monkey::MonkeyArm;
+use crate::physics::{predict_trajectory, Shot, ShotQueue};
+use crate::replay::ReplayPlayback;
+use crate::{GameState, GameplaySet};
+
+/// The color of the dots showing where an arrow will fly.
+const TRAJECTORY_COLOR: Color = Color::rgba(0.3, 0.3, 0.3, 0.6);
+/// The number of timesteps between the dots.
+const TRAJECTORY_DOT_SPACING: usize = 3;
+const TRAJECTORY_DOT_RADIUS: f32 = 2.0;
+
+/// How far the mouse has to be dragged for a shot at full power.
+const MAX_DRAG: f32 = 150.0;
+/// Shorter drags than this are not a shot, so that a click does not fire an arrow by accident.
+const MIN_DRAG: f32 = 10.0;
+/// Releasing the mouse this close to the monkey cancels the shot.
+const CANCEL_RADIUS: f32 = 40.0;
+
+const AIM_LINE_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const CANCELLED_AIM_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.5);
+/// How long the aim line is at full power.
+const AIM_LINE_LENGTH: f32 = 100.0;
+const POWER_METER_OFFSET: Vec2 = Vec2::new(0.0, 80.0);
+const POWER_METER_SIZE: Vec2 = Vec2::new(60.0, 8.0);
+const POWER_METER_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Aim>().add_systems(
+            Update,
+            (
+                // While a replay is playing the shots come from the replay instead
+                (handle_mouse, draw_aim.after(handle_mouse))
+                    .in_set(GameplaySet)
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+                start_game
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(clicked_or_pressed(KeyCode::Return)),
+                select_level
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+                bevy::window::close_on_esc.run_if(in_state(GameState::MainMenu)),
+                toggle_pause
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                handle_retry
+                    .run_if(in_state(GameState::GameOver))
+                    .run_if(clicked_or_pressed(KeyCode::R)),
+                finish_game.run_if(in_state(GameState::LevelComplete)),
+                return_to_main_menu
+                    .run_if(not(in_state(GameState::MainMenu)))
+                    .run_if(not(in_state(GameState::Playing)))
+                    .run_if(input_just_pressed(KeyCode::M)),
+            ),
+        );
+    }
+}
+
+/// The slingshot gesture in progress, if any.
+#[derive(Resource, Default)]
+pub struct Aim {
+    /// Where the mouse was pressed. The arrow flies in the opposite direction of the drag from here.
+    pub anchor: Option<Vec2>,
+}
+
+/// The position of the mouse cursor in the world.
+#[derive(SystemParam)]
+struct Cursor<'w, 's> {
+    q_windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    q_camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl Cursor<'_, '_> {
+    fn position(&self) -> Option<Vec2> {
+        let mouse_pos = self.q_windows.get_single().ok()?.cursor_position()?;
+        let (camera, camera_transform) = self.q_camera.get_single().ok()?;
+        camera
+            .viewport_to_world(camera_transform, mouse_pos)
+            .map(|ray| ray.origin.truncate())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_mouse(
+    mouse_input: Res<Input<MouseButton>>,
+    cursor: Cursor,
+    mut arm: MonkeyArm,
+    mut aim: ResMut<Aim>,
+    mut shots: ResMut<ShotQueue>,
+    ammo: Res<Ammo>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+) {
+    if mouse_input.just_pressed(MouseButton::Left) {
+        aim.anchor = cursor.position();
+    }
+
+    // Right-clicking cancels the shot, and so does releasing the button while the game was paused
+    let held = mouse_input.pressed(MouseButton::Left);
+    if mouse_input.just_pressed(MouseButton::Right)
+        || !(held || mouse_input.just_released(MouseButton::Left))
+    {
+        aim.anchor = None;
+    }
+
+    let (Some(anchor), Some(mouse_pos)) = (aim.anchor, cursor.position()) else {
+        return;
+    };
+    arm.point_towards(anchor - mouse_pos);
+
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    aim.anchor = None;
+    let Some(hand_position) = arm.hand_position() else {
+        return;
+    };
+
+    let power = levels.0[current_level.index].power;
+    if let Some(shot) = slingshot(&power, hand_position, anchor, mouse_pos) {
+        if shots.0.len() < ammo.arrows as usize {
+            shots.0.push(shot);
+        }
+    }
+}
+
+// Show the aim line and power meter, and where the arrow would fly if the mouse was released now
+#[allow(clippy::too_many_arguments)]
+fn draw_aim(
+    mut gizmos: Gizmos,
+    aim: Res<Aim>,
+    cursor: Cursor,
+    arm: MonkeyArm,
+    ammo: Res<Ammo>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    arena: Res<Arena>,
+    time_step: Res<FixedTime>,
+) {
+    let (Some(anchor), Some(mouse_pos), Some(monkey_pos), Some(position)) = (
+        aim.anchor,
+        cursor.position(),
+        arm.monkey_position(),
+        arm.hand_position(),
+    ) else {
+        return;
+    };
+    if ammo.arrows == 0 {
+        return;
+    }
+
+    let level = &levels.0[current_level.index];
+    let meter_center = monkey_pos + POWER_METER_OFFSET;
+    gizmos.rect_2d(meter_center, 0.0, POWER_METER_SIZE, POWER_METER_COLOR);
+
+    let Some(shot) = slingshot(&level.power, position, anchor, mouse_pos) else {
+        gizmos.line_2d(
+            position,
+            position + (anchor - mouse_pos),
+            CANCELLED_AIM_COLOR,
+        );
+        return;
+    };
+
+    let fraction = power_fraction(&level.power, shot.velocity.length());
+    let direction = shot.velocity.normalize_or_zero();
+    gizmos.line_2d(
+        position,
+        position + direction * AIM_LINE_LENGTH * fraction.max(0.1),
+        AIM_LINE_COLOR,
+    );
+
+    // Fill the power meter from the left, going from green to red
+    let meter_color = Color::rgb(fraction, 1.0 - fraction, 0.0);
+    let meter_min = meter_center - POWER_METER_SIZE / 2.0;
+    let fill_width = POWER_METER_SIZE.x * fraction;
+    for y in 1..POWER_METER_SIZE.y as i32 {
+        let y = meter_min.y + y as f32;
+        gizmos.line_2d(
+            Vec2::new(meter_min.x, y),
+            Vec2::new(meter_min.x + fill_width, y),
+            meter_color,
+        );
+    }
+
+    for position in predict_trajectory(shot, &time_step)
+        .take(level.preview.ticks())
+        .take_while(|position| arena.contains(*position))
+        .step_by(TRAJECTORY_DOT_SPACING)
+    {
+        gizmos.circle_2d(position, TRAJECTORY_DOT_RADIUS, TRAJECTORY_COLOR);
+    }
+}
+
+/// The shot fired by dragging the mouse from `anchor` to `mouse_pos`, like a slingshot.
+///
+/// There is no shot if the drag is very short or ends on the hand of the monkey at `launch_position`.
+fn slingshot(power: &Power, launch_position: Vec2, anchor: Vec2, mouse_pos: Vec2) -> Option<Shot> {
+    let drag = anchor - mouse_pos;
+    if drag.length() < MIN_DRAG || mouse_pos.distance(launch_position) < CANCEL_RADIUS {
+        return None;
+    }
+
+    let fraction = (drag.length() / MAX_DRAG).min(1.0);
+    Some(Shot {
+        position: launch_position,
+        velocity: drag.normalize() * (power.min + (power.max - power.min) * fraction),
+    })
+}
+
+/// How much of the available power a shot at `speed` uses, from 0 to 1.
+fn power_fraction(power: &Power, speed: f32) -> f32 {
+    if power.max > power.min {
+        ((speed - power.min) / (power.max - power.min)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+/// The keys for choosing which level to start from in the main menu.
+const LEVEL_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+fn select_level(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    levels: Res<Levels>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let chosen = LEVEL_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    if let Some(index) = chosen.filter(|index| *index < levels.0.len()) {
+        start_game_at(&mut commands, &mut next_state, index);
+    }
+}
+
+fn handle_retry(mut retry_events: EventWriter<RetryLevelEvent>) {
+    retry_events.send_default();
+}
+
+fn clicked_or_pressed(
+    key: KeyCode,
+) -> impl FnMut(Res<Input<MouseButton>>, Res<Input<KeyCode>>) -> bool {
+    move |mouse_input, keyboard_input| {
+        mouse_input.just_released(MouseButton::Left) || keyboard_input.just_pressed(key)
+    }
+}
+
+fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::P]) {
+        next_state.set(match state.get() {
+            GameState::Paused => GameState::Playing,
+            _ => GameState::Paused,
+        });
+    }
+}
+
+// After the last level there is nothing left but to go back to the main menu
+fn finish_game(
+    mouse_input: Res<Input<MouseButton>>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if mouse_input.just_released(MouseButton::Left) && current_level.index + 1 == levels.0.len() {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn return_to_main_menu(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::MainMenu);
+}
@@ -0,0 +1,677 @@
+//! Levels describe the arena, the monkey and the balloons, and are loaded from `.level.ron` files.
+//! The game plays through a list of levels in order, moving on once all balloons are popped.
+//!
+//! ```ron
+//! (
+//!     name: "Example",
+//!     arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+//!     walls: (top: Bounce(restitution: 0.5), bottom: Destroy),
+//!     monkey: (-330.0, 60.0),
+//!     arrows: 12,
+//!     pierce: Some(3),
+//!     preview: Ticks(20),
+//!     power: (min: 200.0, max: 1200.0),
+//!     scoring: (arrow_bonus: 10, time_limit: 30.0),
+//!     balloons: [
+//!         At((0.0, 150.0)),
+//!         Special(Layered(layers: 3), (50.0, 150.0)),
+//!         Special(Explosive(radius: 80.0), (300.0, -100.0)),
+//!         Moving(position: (0.0, 0.0), path: Circle(radius: 50.0, period: 4.0)),
+//!         Moving(position: (100.0, 0.0), path: Bob(height: 20.0, period: 2.0), kind: Metal),
+//!         Random(count: 10, min: (200.0, 0.0), max: (400.0, 200.0)),
+//!         Random(count: 2, min: (200.0, 0.0), max: (400.0, 200.0), kind: Bonus(arrows: 2)),
+//!         Random(
+//!             count: 3,
+//!             min: (-100.0, -200.0),
+//!             max: (100.0, -100.0),
+//!             path: Some(Line(offset: (0.0, 80.0), period: 3.0)),
+//!         ),
+//!     ],
+//!     obstacles: [
+//!         (position: (100.0, 0.0), size: (20.0, 200.0), behaviour: Bounce(restitution: 0.8)),
+//!         // A ramp, which needs the rapier physics backend
+//!         (position: (0.0, -200.0), size: (200.0, 20.0), angle: 0.3),
+//!     ],
+//!     // Only swings with the rapier physics backend
+//!     chains: [(anchor: (-200.0, 295.0), links: 5, link_size: (8.0, 30.0))],
+//! )
+//! ```
+
+use std::fmt;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+use bevy_prng::ChaCha8Rng;
+use rand_core::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::arena::{Arena, Chain, Obstacle, WallBundle, WallLocation, Walls};
+use crate::balloons::BalloonKind;
+use crate::monkey::bow_bundle;
+use crate::paths::{BalloonPath, Moving};
+use crate::physics::{fire_shots, Collider, CollisionFlushSet, PhysicsBackend};
+use crate::rapier;
+use crate::replay::ReplayPlayback;
+use crate::scoring::ScoringRules;
+use crate::{GameState, GameplaySet, Seed, Textures};
+
+/// The levels played when no other levels are given, in order.
+pub const LEVEL_PATHS: [&str; 3] = [
+    "levels/01.level.ron",
+    "levels/02.level.ron",
+    "levels/03.level.ron",
+];
+
+/// How long the results of a level are shown before the next level starts.
+const RESULTS_DURATION: f32 = 3.0;
+
+/// The diameter of a balloon, which is the scale of its transform.
+const BALLOON_SIZE: f32 = 32.0;
+
+/// Spawns the entities of the [`CurrentLevel`], and moves on to the next level once it is complete.
+pub struct LevelPlugin {
+    /// The levels to play. If `None` they are loaded from [`LEVEL_PATHS`].
+    pub levels: Option<Vec<Level>>,
+    /// Whether an `AssetServer` is available to load levels with.
+    pub use_assets: bool,
+}
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RetryLevelEvent>()
+            .init_resource::<LevelTick>()
+            .add_systems(
+                PreUpdate,
+                (despawn_level, setup)
+                    .chain()
+                    .run_if(resource_exists_and_changed::<CurrentLevel>()),
+            )
+            .add_systems(Update, finish_loading.run_if(in_state(GameState::Loading)))
+            .add_systems(OnEnter(GameState::MainMenu), (despawn_level, end_game))
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_level_tick.before(fire_shots),
+                    // Balloons popped in this timestep must be gone before checking what is left
+                    check_level_complete.after(CollisionFlushSet),
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnEnter(GameState::LevelComplete), start_results_timer)
+            .add_systems(
+                Update,
+                (
+                    advance_to_next_level.run_if(in_state(GameState::LevelComplete)),
+                    retry_level.run_if(in_state(GameState::GameOver)),
+                ),
+            );
+
+        if self.use_assets {
+            app.add_asset::<Level>().init_asset_loader::<LevelLoader>();
+        }
+
+        match (&self.levels, self.use_assets) {
+            (Some(levels), _) => {
+                app.insert_resource(Levels(levels.clone()));
+            }
+            (None, true) => {
+                app.add_systems(Startup, load_levels).add_systems(
+                    Update,
+                    insert_loaded_levels
+                        .before(finish_loading)
+                        .run_if(in_state(GameState::Loading)),
+                );
+            }
+            (None, false) => {
+                app.insert_resource(Levels(Level::builtin()));
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, TypeUuid, TypePath, Clone, PartialEq, Debug)]
+#[uuid = "3c0b9e8e-6f07-4d7c-9a51-5b7e2c1f8a42"]
+pub struct Level {
+    /// Identifies the level in the saved records, so it should stay the same when the level
+    /// is changed or moved. Levels without a name don't keep records.
+    #[serde(default)]
+    pub name: String,
+    pub arena: Arena,
+    pub monkey: Vec2,
+    pub balloons: Vec<BalloonSpawn>,
+    /// How many arrows the player gets.
+    pub arrows: u32,
+    /// How many balloons each arrow can pop, or `None` for no limit.
+    #[serde(default)]
+    pub pierce: Option<u32>,
+    /// How much of the path of an arrow is shown while aiming.
+    #[serde(default)]
+    pub preview: TrajectoryPreview,
+    /// How fast arrows can be shot.
+    #[serde(default)]
+    pub power: Power,
+    /// What happens to arrows hitting each wall. Walls not mentioned make arrows stick.
+    #[serde(default)]
+    pub walls: Walls,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub chains: Vec<Chain>,
+    /// How points are given, on top of the points of each balloon.
+    #[serde(default)]
+    pub scoring: ScoringRules,
+}
+
+impl Level {
+    pub fn from_ron(text: &str) -> Result<Level, LevelError> {
+        let level: Level = ron::from_str(text)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// Checks what can't be expressed by the types alone.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        if self.arena.left >= self.arena.right || self.arena.bottom >= self.arena.top {
+            return Err(LevelError::InvalidArena(self.arena));
+        }
+        if self.arrows == 0 {
+            return Err(LevelError::NoArrows);
+        }
+        if self.pierce == Some(0) {
+            return Err(LevelError::NoPierce);
+        }
+        Ok(())
+    }
+
+    /// The levels at [`LEVEL_PATHS`], embedded in the binary for use without an `AssetServer`.
+    pub fn builtin() -> Vec<Level> {
+        [
+            include_str!("../assets/levels/01.level.ron"),
+            include_str!("../assets/levels/02.level.ron"),
+            include_str!("../assets/levels/03.level.ron"),
+        ]
+        .into_iter()
+        .map(|text| Level::from_ron(text).expect("the builtin levels should be valid"))
+        .collect()
+    }
+}
+
+/// Why a level could not be read.
+#[derive(Debug)]
+pub enum LevelError {
+    Ron(ron::error::SpannedError),
+    /// The left side of the arena isn't left of the right side, or the bottom isn't below the top.
+    InvalidArena(Arena),
+    /// The player gets no arrows, so the level could never end.
+    NoArrows,
+    /// The arrows can't pop any balloons.
+    NoPierce,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Ron(err) => err.fmt(f),
+            LevelError::InvalidArena(arena) => write!(f, "the arena {arena:?} has no area"),
+            LevelError::NoArrows => write!(f, "the level has no arrows"),
+            LevelError::NoPierce => write!(f, "the arrows can't pop any balloons"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LevelError::Ron(err) => Some(err),
+            LevelError::InvalidArena(_) | LevelError::NoArrows | LevelError::NoPierce => None,
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        LevelError::Ron(err)
+    }
+}
+
+/// Where to place balloons in a level.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum BalloonSpawn {
+    /// A single normal balloon at the given position.
+    At(Vec2),
+    /// A single balloon of the given kind at the given position.
+    Special(BalloonKind, Vec2),
+    /// A single balloon moving along a path, starting from the given position.
+    Moving {
+        position: Vec2,
+        path: BalloonPath,
+        #[serde(default)]
+        kind: BalloonKind,
+    },
+    /// Balloons at random positions within a rectangle, chosen using the seeded RNG.
+    Random {
+        count: u32,
+        min: Vec2,
+        max: Vec2,
+        #[serde(default)]
+        kind: BalloonKind,
+        /// The path all of the balloons move along, each from its own position.
+        #[serde(default)]
+        path: Option<BalloonPath>,
+    },
+}
+
+/// How much of the predicted path of an arrow is shown while aiming.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TrajectoryPreview {
+    /// The whole path until the arrow leaves the arena.
+    #[default]
+    Full,
+    /// Only the given number of timesteps of the path.
+    Ticks(u32),
+    Disabled,
+}
+
+impl TrajectoryPreview {
+    /// The maximum number of timesteps to show.
+    pub fn ticks(&self) -> usize {
+        match self {
+            TrajectoryPreview::Full => usize::MAX,
+            TrajectoryPreview::Ticks(ticks) => *ticks as usize,
+            TrajectoryPreview::Disabled => 0,
+        }
+    }
+}
+
+/// The range of speeds arrows can be shot with, in pixels per second.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Power {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Power {
+            min: 100.0,
+            max: 1000.0,
+        }
+    }
+}
+
+/// All levels of the game, in the order they are played.
+#[derive(Resource)]
+pub struct Levels(pub Vec<Level>);
+
+/// The level being played.
+///
+/// Changing it replaces the entities of the old level with those of the new one.
+#[derive(Resource)]
+pub struct CurrentLevel {
+    /// An index into [`Levels`].
+    pub index: usize,
+    /// How many times the level has been retried.
+    pub attempt: u32,
+}
+
+/// Sent to play the current level again from the start.
+#[derive(Event, Default)]
+pub struct RetryLevelEvent;
+
+/// The number of fixed timesteps played in the current level.
+///
+/// Unlike [`SimulationTick`](crate::physics::SimulationTick) it only advances while playing,
+/// so it does not depend on how long loading or the results screen took.
+#[derive(Resource, Default)]
+pub struct LevelTick(pub u64);
+
+/// Marks entities that belong to the current level, and are despawned when it ends.
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level: Level = ron::de::from_bytes(bytes)?;
+            level.validate()?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct LevelHandles(Vec<Handle<Level>>);
+
+fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = LEVEL_PATHS
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+    commands.insert_resource(LevelHandles(handles));
+}
+
+fn insert_loaded_levels(
+    mut commands: Commands,
+    handles: Option<Res<LevelHandles>>,
+    assets: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+
+    let mut levels = Vec::new();
+    for (handle, path) in handles.0.iter().zip(LEVEL_PATHS) {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => levels.extend(assets.get(handle).cloned()),
+            // The reason has already been logged by the asset server
+            LoadState::Failed => error!("Skipping level {path}, which could not be loaded"),
+            _ => return,
+        }
+    }
+
+    if levels.is_empty() {
+        error!("None of the levels could be loaded, playing the builtin ones instead");
+        levels = Level::builtin();
+    }
+    commands.insert_resource(Levels(levels));
+    commands.remove_resource::<LevelHandles>();
+}
+
+fn finish_loading(levels: Option<Res<Levels>>, mut next_state: ResMut<NextState<GameState>>) {
+    if levels.is_some() {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+/// Starts a new game from the first level,
+/// or from the level the replay being played back was started from.
+pub fn start_game(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    playback: Option<Res<ReplayPlayback>>,
+    levels: Res<Levels>,
+) {
+    let mut index = playback.map_or(0, |playback| playback.start_level());
+    if index >= levels.0.len() {
+        // Levels that could not be loaded are skipped, so there can be fewer than expected
+        error!(
+            "The replay starts from level {}, but only {} levels were loaded, starting from the first one instead",
+            index + 1,
+            levels.0.len(),
+        );
+        index = 0;
+    }
+    start_game_at(&mut commands, &mut next_state, index);
+}
+
+/// Starts a new game from the level at `index` in [`Levels`].
+pub fn start_game_at(commands: &mut Commands, next_state: &mut NextState<GameState>, index: usize) {
+    commands.insert_resource(CurrentLevel { index, attempt: 0 });
+    next_state.set(GameState::Playing);
+}
+
+/// Leaves the current level when returning to the main menu.
+pub fn end_game(mut commands: Commands) {
+    commands.remove_resource::<CurrentLevel>();
+}
+
+pub fn advance_level_tick(mut tick: ResMut<LevelTick>) {
+    tick.0 += 1;
+}
+
+pub fn check_level_complete(
+    balloons: Query<&BalloonKind, With<Balloon>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !balloons.iter().any(BalloonKind::must_pop) {
+        next_state.set(GameState::LevelComplete);
+    }
+}
+
+#[derive(Resource)]
+struct ResultsTimer(Timer);
+
+fn start_results_timer(mut commands: Commands) {
+    commands.insert_resource(ResultsTimer(Timer::from_seconds(
+        RESULTS_DURATION,
+        TimerMode::Once,
+    )));
+}
+
+fn advance_to_next_level(
+    time: Res<Time>,
+    mut timer: ResMut<ResultsTimer>,
+    levels: Res<Levels>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // After the last level the results stay on screen
+    if timer.0.tick(time.delta()).just_finished() && current_level.index + 1 < levels.0.len() {
+        current_level.index += 1;
+        current_level.attempt = 0;
+        next_state.set(GameState::Playing);
+    }
+}
+
+#[derive(Component)]
+pub struct Monkey;
+
+#[derive(Component)]
+pub struct Balloon;
+
+fn retry_level(
+    mut retry_events: EventReader<RetryLevelEvent>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if retry_events.iter().next().is_some() {
+        current_level.attempt += 1;
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn despawn_level(mut commands: Commands, query: Query<Entity, With<LevelEntity>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Add the entities of the current level to our world
+fn setup(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    textures: Res<Textures>,
+    backend: Res<PhysicsBackend>,
+    seed: Res<Seed>,
+    mut tick: ResMut<LevelTick>,
+) {
+    let use_rapier = *backend == PhysicsBackend::Rapier;
+
+    tick.0 = 0;
+
+    let level = &levels.0[current_level.index];
+    commands.insert_resource(level.arena);
+
+    // Every level gets its own generator, so that its layout doesn't depend on how often the
+    // levels before it were played
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.0 ^ current_level.index as u64);
+
+    // Monkey
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(1.0, 1.0)),
+                    ..Default::default()
+                },
+                texture: textures.monkey.clone(),
+                transform: Transform {
+                    translation: level.monkey.extend(0.0),
+                    scale: Vec3::new(128.0, 128.0, 1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Monkey,
+            LevelEntity,
+        ))
+        .with_children(|monkey| {
+            monkey.spawn(bow_bundle(textures.monkey_bow.clone()));
+        });
+
+    // Walls
+    for location in [
+        WallLocation::Left,
+        WallLocation::Right,
+        WallLocation::Bottom,
+        WallLocation::Top,
+    ] {
+        let behaviour = level.walls.behaviour(&location);
+        let bundle = WallBundle::new(location, &level.arena, &level.walls);
+        let size = bundle.size();
+        let mut wall = commands.spawn((bundle, LevelEntity));
+        if use_rapier {
+            wall.insert(rapier::wall_body(behaviour, size, 0.0));
+        }
+    }
+
+    for obstacle in &level.obstacles {
+        let mut wall = commands.spawn((WallBundle::obstacle(obstacle), LevelEntity));
+        if use_rapier {
+            wall.insert(rapier::wall_body(
+                obstacle.behaviour,
+                obstacle.size,
+                obstacle.spin,
+            ));
+        }
+    }
+
+    for chain in &level.chains {
+        let mut parent = use_rapier.then(|| {
+            let anchor = commands.spawn((rapier::chain_anchor_body(chain.anchor), LevelEntity));
+            (anchor.id(), Vec2::ZERO)
+        });
+        for index in 0..chain.links {
+            let mut link = commands.spawn((WallBundle::chain_link(chain, index), LevelEntity));
+            if let Some((parent_entity, parent_anchor)) = parent {
+                link.insert(rapier::chain_link_body(
+                    chain.behaviour,
+                    chain.link_size,
+                    parent_entity,
+                    parent_anchor,
+                ));
+                parent = Some((link.id(), Vec2::new(0.0, -chain.link_size.y / 2.0)));
+            }
+        }
+    }
+
+    for spawn in &level.balloons {
+        match spawn {
+            BalloonSpawn::At(position) => spawn_balloon(
+                &mut commands,
+                &textures,
+                use_rapier,
+                BalloonKind::Normal,
+                *position,
+                None,
+            ),
+            BalloonSpawn::Special(kind, position) => {
+                spawn_balloon(&mut commands, &textures, use_rapier, *kind, *position, None)
+            }
+            BalloonSpawn::Moving {
+                position,
+                path,
+                kind,
+            } => spawn_balloon(
+                &mut commands,
+                &textures,
+                use_rapier,
+                *kind,
+                *position,
+                Some(path),
+            ),
+            BalloonSpawn::Random {
+                count,
+                min,
+                max,
+                kind,
+                path,
+            } => {
+                let size = (*max - *min).as_uvec2().max(UVec2::ONE);
+                for _ in 0..*count {
+                    let balloon_position = *min
+                        + Vec2::new(
+                            (rng.next_u32() % size.x) as f32,
+                            (rng.next_u32() % size.y) as f32,
+                        );
+                    spawn_balloon(
+                        &mut commands,
+                        &textures,
+                        use_rapier,
+                        *kind,
+                        balloon_position,
+                        path.as_ref(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn spawn_balloon(
+    commands: &mut Commands,
+    textures: &Textures,
+    use_rapier: bool,
+    kind: BalloonKind,
+    position: Vec2,
+    path: Option<&BalloonPath>,
+) {
+    let start_position = position + path.map_or(Vec2::ZERO, |path| path.offset(0.0));
+    let mut balloon = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: kind.color(),
+                custom_size: Some(Vec2::new(1.0, 1.0)),
+                ..Default::default()
+            },
+            texture: textures.balloon.clone(),
+            transform: Transform {
+                translation: start_position.extend(0.0),
+                scale: Vec3::new(BALLOON_SIZE, BALLOON_SIZE, 1.0),
+                ..default()
+            },
+            ..default()
+        },
+        Balloon,
+        kind,
+        Collider,
+        LevelEntity,
+    ));
+    if let Some(path) = path {
+        balloon.insert(Moving {
+            origin: position,
+            path: path.clone(),
+        });
+    }
+    if use_rapier {
+        rapier::insert_balloon_body(&mut balloon, kind, BALLOON_SIZE);
+    }
+}
@@ -0,0 +1,257 @@
+//! A remake of the game "Bloons" built on the Bevy game engine.
+//!
+//! The whole game is available as [`BloonsPlugin`], which can be added to any `App`.
+//! It is made up of smaller plugins (physics, input, scoring, audio and UI)
+//! that can also be added one by one.
+//!
+//! For running the game without a window, see the [`headless`] module.
+
+use std::path::PathBuf;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_prng::ChaCha8Rng;
+use bevy_rand::prelude::*;
+use rand_core::SeedableRng;
+
+pub mod ammo;
+pub mod arena;
+pub mod args;
+pub mod audio;
+pub mod balloons;
+pub mod effects;
+pub mod headless;
+pub mod input;
+pub mod level;
+pub mod monkey;
+pub mod paths;
+pub mod physics;
+pub mod rapier;
+pub mod records;
+pub mod replay;
+pub mod scoring;
+pub mod seed;
+pub mod storage;
+pub mod ui;
+
+pub use ammo::AmmoPlugin;
+pub use audio::SoundPlugin;
+pub use balloons::BalloonPlugin;
+pub use effects::EffectsPlugin;
+pub use input::InputPlugin;
+pub use level::{Level, LevelPlugin};
+pub use monkey::MonkeyPlugin;
+pub use paths::PathPlugin;
+pub use physics::{PhysicsBackend, PhysicsPlugin};
+pub use records::RecordsPlugin;
+pub use replay::{Replay, ReplayPlugin};
+pub use scoring::ScoringPlugin;
+pub use seed::Seed;
+pub use ui::HudPlugin;
+
+/// The overall state of the game.
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+    /// Waiting for the levels to be loaded.
+    #[default]
+    Loading,
+    MainMenu,
+    Playing,
+    /// The game is frozen until the player resumes it.
+    Paused,
+    /// All balloons of the current level have been popped and the results are shown.
+    LevelComplete,
+    /// The player ran out of arrows before popping all balloons, and may retry the level.
+    GameOver,
+}
+
+/// Systems simulating the game, which only run while playing.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct GameplaySet;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+/// Adds the complete game to an `App`.
+///
+/// By default it expects `DefaultPlugins` (or an equivalent set of plugins) to already be added.
+/// A plugin created with [`BloonsPlugin::headless`] only needs `MinimalPlugins`.
+///
+/// Unless a seed is given with [`BloonsPlugin::with_seed`], a random one is chosen.
+/// Either way the seed in use is available as the [`Seed`] resource.
+#[derive(Default)]
+pub struct BloonsPlugin {
+    headless: bool,
+    seed: Option<Seed>,
+    physics: PhysicsBackend,
+    levels: Option<Vec<Level>>,
+    replay: Option<Replay>,
+    replay_save_path: Option<PathBuf>,
+}
+
+impl BloonsPlugin {
+    /// Only the gameplay simulation, without input, audio, UI or assets.
+    ///
+    /// Time advances by exactly one fixed timestep per `App::update`,
+    /// so the simulation runs as fast as it is updated.
+    /// Unless other levels are given, the builtin levels are played.
+    pub fn headless() -> Self {
+        BloonsPlugin {
+            headless: true,
+            ..default()
+        }
+    }
+
+    /// Seeds the random number generator, making the generated levels reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(Seed(seed));
+        self
+    }
+
+    /// Simulates the arrows with the given physics backend instead of the builtin one.
+    pub fn with_physics(mut self, backend: PhysicsBackend) -> Self {
+        self.physics = backend;
+        self
+    }
+
+    /// Plays only the given level instead of the default ones.
+    pub fn with_level(self, level: Level) -> Self {
+        self.with_levels(vec![level])
+    }
+
+    /// Plays the given levels, in order, instead of the default ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is empty.
+    pub fn with_levels(mut self, levels: Vec<Level>) -> Self {
+        assert!(!levels.is_empty(), "there has to be at least one level");
+        self.levels = Some(levels);
+        self
+    }
+
+    /// Plays back the shots of a replay instead of taking input from the mouse.
+    ///
+    /// This also uses the seed and physics backend of the replay.
+    /// If the levels are known when the plugin is built, that is in headless mode or when they
+    /// were given with [`BloonsPlugin::with_levels`], building it panics if the replay starts
+    /// from a level that doesn't exist.
+    pub fn with_replay(mut self, replay: Replay) -> Self {
+        self.seed = Some(Seed(replay.seed));
+        self.physics = replay.physics;
+        self.replay = Some(replay);
+        self
+    }
+
+    /// Saves the replay of the game to a file when the app exits.
+    pub fn saving_replay_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.replay_save_path = Some(path.into());
+        self
+    }
+}
+
+impl Plugin for BloonsPlugin {
+    fn build(&self, app: &mut App) {
+        // Configure how frequently our gameplay systems are run
+        let fixed_time = FixedTime::new_from_secs(1.0 / 60.0);
+        let time_step = fixed_time.period;
+
+        let level_count = match &self.levels {
+            Some(levels) => Some(levels.len()),
+            None if self.headless => Some(level::LEVEL_PATHS.len()),
+            // The levels are loaded later on, and checked once the game starts
+            None => None,
+        };
+        if let (Some(replay), Some(level_count)) = (&self.replay, level_count) {
+            assert!(
+                replay.start_level < level_count,
+                "the replay starts from level {}, but there are only {level_count} levels",
+                replay.start_level + 1,
+            );
+        }
+
+        let seed = self.seed.unwrap_or_else(Seed::random);
+        info!("Using seed {}", seed.0);
+
+        app.add_plugins(EntropyPlugin::<ChaCha8Rng>::default())
+            .insert_resource(GlobalEntropy::<ChaCha8Rng>::seed_from_u64(seed.0))
+            .insert_resource(seed)
+            .insert_resource(fixed_time)
+            .add_state::<GameState>()
+            .configure_set(
+                FixedUpdate,
+                GameplaySet.run_if(in_state(GameState::Playing)),
+            )
+            .configure_set(Update, GameplaySet.run_if(in_state(GameState::Playing)))
+            .add_plugins((
+                LevelPlugin {
+                    levels: self.levels.clone(),
+                    use_assets: !self.headless,
+                },
+                PhysicsPlugin {
+                    backend: self.physics,
+                },
+                BalloonPlugin,
+                PathPlugin,
+                AmmoPlugin,
+                ScoringPlugin,
+                ReplayPlugin {
+                    playback: self.replay.clone(),
+                    save_path: self.replay_save_path.clone(),
+                },
+            ));
+
+        if self.headless {
+            // There is nobody to click through the main menu.
+            // The new game must start after the old one ended, or its level would be removed again.
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(time_step))
+                .init_resource::<Textures>()
+                .add_systems(
+                    OnEnter(GameState::MainMenu),
+                    level::start_game.after(level::end_game),
+                );
+        } else {
+            app.insert_resource(ClearColor(BACKGROUND_COLOR))
+                .add_systems(PreStartup, load_textures)
+                .add_plugins((
+                    InputPlugin,
+                    MonkeyPlugin,
+                    EffectsPlugin,
+                    SoundPlugin,
+                    RecordsPlugin,
+                    HudPlugin,
+                ));
+        }
+    }
+}
+
+/// The textures used by sprites in the game.
+///
+/// In headless mode nothing is loaded and all handles are left at their default.
+#[derive(Resource, Default)]
+pub struct Textures {
+    pub monkey: Handle<Image>,
+    /// The sprite sheet of the bow arm of the monkey, see the [`monkey`] module.
+    pub monkey_bow: Handle<TextureAtlas>,
+    pub balloon: Handle<Image>,
+    pub arrow: Handle<Image>,
+}
+
+fn load_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let monkey_bow = TextureAtlas::from_grid(
+        asset_server.load("textures/monkey_bow.png"),
+        monkey::MONKEY_TEXTURE_SIZE,
+        monkey::BOW_FRAMES,
+        1,
+        None,
+        None,
+    );
+    commands.insert_resource(Textures {
+        monkey: asset_server.load("textures/monkey_body.png"),
+        monkey_bow: texture_atlases.add(monkey_bow),
+        balloon: asset_server.load("textures/balloon.png"),
+        arrow: asset_server.load("textures/arrow.png"),
+    });
+}
@@ -0,0 +1,143 @@
+//! The bow arm of the monkey, which turns towards where the player is aiming
+//! and plays a throw animation whenever an arrow is fired.
+//!
+//! The arm is drawn from a sprite sheet on top of the body of the monkey. Every frame of the
+//! sheet has the same size and layout as the body texture, so they line up when drawn together.
+
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::Anchor};
+
+use crate::level::{LevelEntity, Monkey};
+use crate::physics::ShotFiredEvent;
+use crate::GameplaySet;
+
+/// The size in pixels of the monkey texture and of each frame of the bow sheet.
+pub const MONKEY_TEXTURE_SIZE: Vec2 = Vec2::new(64.0, 64.0);
+/// The number of frames in the bow sheet.
+pub const BOW_FRAMES: usize = 4;
+
+/// The frames of the throw animation, after which the bow is back at frame 0 with a new arrow.
+const THROW_FRAMES: [usize; 3] = [1, 2, 3];
+const THROW_FRAME_DURATION: f32 = 0.08;
+
+/// The pixel of the textures at the shoulder, around which the arm turns.
+const SHOULDER: Vec2 = Vec2::new(28.5, 34.5);
+/// The pixel of each frame of the bow sheet where the hand holds the arrow.
+const HAND_ANCHORS: [Vec2; BOW_FRAMES] = [
+    Vec2::new(43.5, 23.5),
+    Vec2::new(44.5, 22.5),
+    Vec2::new(43.5, 23.5),
+    Vec2::new(42.5, 24.5),
+];
+
+pub struct MonkeyPlugin;
+
+impl Plugin for MonkeyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (start_throw, animate_throw.after(start_throw)).in_set(GameplaySet),
+        );
+    }
+}
+
+/// The bow arm of the monkey, a child of the [`Monkey`] entity.
+#[derive(Component, Default)]
+pub struct Bow {
+    /// The index into [`THROW_FRAMES`] and the timer of the throw animation, if it is playing.
+    throw: Option<(usize, Timer)>,
+}
+
+/// The components of the bow arm of a monkey, drawn with `sheet`.
+pub fn bow_bundle(sheet: Handle<TextureAtlas>) -> impl Bundle {
+    (
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: 0,
+                anchor: Anchor::Custom(texture_point(SHOULDER)),
+                custom_size: Some(Vec2::new(1.0, 1.0)),
+                ..default()
+            },
+            texture_atlas: sheet,
+            // The monkey is scaled to the size of its texture, so the shoulders of the body and
+            // the arm line up when the arm is placed at the shoulder, just in front of the body
+            transform: Transform::from_translation(texture_point(SHOULDER).extend(0.1)),
+            ..default()
+        },
+        Bow::default(),
+        LevelEntity,
+    )
+}
+
+/// Converts a pixel of the monkey textures, counted from the top left,
+/// to a position relative to the center of the monkey in units of its size.
+fn texture_point(pixel: Vec2) -> Vec2 {
+    let point = pixel / MONKEY_TEXTURE_SIZE - 0.5;
+    Vec2::new(point.x, -point.y)
+}
+
+/// The arm of the monkey, for aiming and finding where arrows are launched from.
+#[derive(SystemParam)]
+pub struct MonkeyArm<'w, 's> {
+    monkeys: Query<'w, 's, &'static Transform, (With<Monkey>, Without<Bow>)>,
+    bows: Query<'w, 's, (&'static mut Transform, &'static TextureAtlasSprite), With<Bow>>,
+}
+
+impl MonkeyArm<'_, '_> {
+    /// Turns the arm to point the arrow in `direction`.
+    pub fn point_towards(&mut self, direction: Vec2) {
+        let Ok((mut transform, _)) = self.bows.get_single_mut() else {
+            return;
+        };
+        if direction != Vec2::ZERO {
+            let rest_direction = HAND_ANCHORS[0] - SHOULDER;
+            let angle = Vec2::new(rest_direction.x, -rest_direction.y).angle_between(direction);
+            transform.rotation = Quat::from_rotation_z(angle);
+        }
+    }
+
+    pub fn monkey_position(&self) -> Option<Vec2> {
+        let monkey = self.monkeys.get_single().ok()?;
+        Some(monkey.translation.truncate())
+    }
+
+    /// Where the hand holding the arrow is in the world, which is where arrows are launched from.
+    pub fn hand_position(&self) -> Option<Vec2> {
+        let monkey = self.monkeys.get_single().ok()?;
+        let Ok((bow, sprite)) = self.bows.get_single() else {
+            return Some(monkey.translation.truncate());
+        };
+
+        // The sprite is drawn with its anchor at the origin of the arm
+        let hand = texture_point(HAND_ANCHORS[sprite.index]) - texture_point(SHOULDER);
+        let arm = monkey.mul_transform(*bow);
+        Some(arm.transform_point(hand.extend(0.0)).truncate())
+    }
+}
+
+fn start_throw(mut fired_events: EventReader<ShotFiredEvent>, mut query: Query<&mut Bow>) {
+    if fired_events.iter().next().is_some() {
+        for mut bow in &mut query {
+            bow.throw = Some((
+                0,
+                Timer::from_seconds(THROW_FRAME_DURATION, TimerMode::Repeating),
+            ));
+        }
+    }
+}
+
+fn animate_throw(time: Res<Time>, mut query: Query<(&mut Bow, &mut TextureAtlasSprite)>) {
+    for (mut bow, mut sprite) in &mut query {
+        let Some((frame, timer)) = &mut bow.throw else {
+            sprite.index = 0;
+            continue;
+        };
+
+        if timer.tick(time.delta()).just_finished() {
+            *frame += 1;
+        }
+        match THROW_FRAMES.get(*frame) {
+            Some(&index) => sprite.index = index,
+            None => bow.throw = None,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Balloons that move along paths.
+//!
+//! The position of a moving balloon only depends on how many fixed timesteps the level has been
+//! played for, so the balloons are always in the same place when a replay is played back.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::PhysicsSet;
+use serde::{Deserialize, Serialize};
+
+use crate::level::{advance_level_tick, LevelTick};
+use crate::physics::{fire_shots, CollisionSet};
+use crate::GameplaySet;
+
+pub struct PathPlugin;
+
+impl Plugin for PathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            move_balloons
+                .after(advance_level_tick)
+                .before(fire_shots)
+                .before(PhysicsSet::SyncBackend)
+                .before(CollisionSet)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// How a balloon moves, relative to where it was placed. All periods are in seconds.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum BalloonPath {
+    /// Drifts to `offset` and back again.
+    Line { offset: Vec2, period: f32 },
+    /// Goes around a circle centered where the balloon was placed, starting on its right side.
+    /// A negative period goes clockwise.
+    Circle { radius: f32, period: f32 },
+    /// Loops through `points` along a smooth curve, starting and ending where the balloon was placed.
+    Spline { points: Vec<Vec2>, period: f32 },
+    /// Bobs up and down by `height`.
+    Bob { height: f32, period: f32 },
+}
+
+impl BalloonPath {
+    /// How far from where it was placed the balloon is after `time` seconds.
+    pub fn offset(&self, time: f32) -> Vec2 {
+        match self {
+            BalloonPath::Line { offset, period } => {
+                // Back and forth at a constant speed
+                let phase = cycle(time, *period);
+                *offset * (1.0 - (2.0 * phase - 1.0).abs())
+            }
+            BalloonPath::Circle { radius, period } => {
+                let angle = cycle(time, *period) * TAU;
+                Vec2::new(angle.cos(), angle.sin()) * *radius
+            }
+            BalloonPath::Spline { points, period } => {
+                let points: Vec<Vec2> = std::iter::once(Vec2::ZERO)
+                    .chain(points.iter().copied())
+                    .collect();
+                let position = cycle(time, *period) * points.len() as f32;
+                let segment = position as usize % points.len();
+                let point = |i: usize| points[(segment + i) % points.len()];
+                catmull_rom(
+                    point(points.len() - 1),
+                    point(0),
+                    point(1),
+                    point(2),
+                    position.fract(),
+                )
+            }
+            BalloonPath::Bob { height, period } => {
+                Vec2::new(0.0, (cycle(time, *period) * TAU).sin() * *height)
+            }
+        }
+    }
+}
+
+/// How far into its current cycle a path with the given period is, from 0 to 1.
+fn cycle(time: f32, period: f32) -> f32 {
+    if period == 0.0 {
+        0.0
+    } else {
+        (time / period).rem_euclid(1.0)
+    }
+}
+
+/// The point `t` of the way from `p1` to `p2` on a Catmull-Rom spline.
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// A balloon moving along `path` from `origin`.
+#[derive(Component)]
+pub struct Moving {
+    pub origin: Vec2,
+    pub path: BalloonPath,
+}
+
+fn move_balloons(
+    tick: Res<LevelTick>,
+    time_step: Res<FixedTime>,
+    mut query: Query<(&Moving, &mut Transform, &mut GlobalTransform)>,
+) {
+    let time = tick.0 as f32 * time_step.period.as_secs_f32();
+    for (moving, mut transform, mut global_transform) in &mut query {
+        let position = moving.origin + moving.path.offset(time);
+        transform.translation = position.extend(transform.translation.z);
+        // Rapier reads the global transform, which is otherwise only updated after the fixed timesteps
+        *global_transform = GlobalTransform::from(*transform);
+    }
+}
@@ -0,0 +1,684 @@
+//! Movement of arrows, collisions between arrows and balloons, and cleaning up arrows that are done.
+//!
+//! Motion and collisions are either simulated by the simple builtin code in this module,
+//! or by rapier (see the [`rapier`](crate::rapier) module), as chosen by [`PhysicsBackend`].
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    sprite::collide_aabb::{collide, Collision},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arena::{Arena, WallBehaviour};
+use crate::balloons::{BalloonKind, METAL_RESTITUTION};
+use crate::level::LevelEntity;
+use crate::rapier::{self, RapierBackendPlugin};
+use crate::{GameplaySet, Textures};
+
+pub const GRAVITY: f32 = 9.82 * 100.0;
+
+/// How many fixed timesteps an arrow stays around before it is removed (10 seconds).
+const ARROW_LIFETIME: u32 = 600;
+
+/// The distance from the tail to the tip of an arrow, which is drawn diagonally across its sprite.
+pub const ARROW_LENGTH: f32 = 40.0;
+/// Half the thickness of the shaft of an arrow.
+pub const ARROW_RADIUS: f32 = 2.0;
+/// The size of the sprite of an arrow, which is the scale of its transform.
+const ARROW_SCALE: f32 = 32.0;
+
+#[derive(Default)]
+pub struct PhysicsPlugin {
+    pub backend: PhysicsBackend,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BalloonHitEvent>()
+            .add_event::<BalloonPopEvent>()
+            .add_event::<ShotFiredEvent>()
+            .add_event::<ShotFinishedEvent>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<ShotQueue>()
+            .insert_resource(self.backend)
+            .configure_sets(
+                FixedUpdate,
+                (
+                    CollisionSet.in_set(GameplaySet),
+                    CollisionFlushSet.after(CollisionSet).in_set(GameplaySet),
+                ),
+            )
+            // Add our gameplay simulation systems to the fixed timestep schedule
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_tick.before(fire_shots),
+                    apply_deferred.in_set(CollisionFlushSet),
+                    (
+                        fire_shots.before(CollisionSet),
+                        despawn_finished_arrows.after(CollisionFlushSet),
+                    )
+                        .in_set(GameplaySet),
+                ),
+            )
+            .add_systems(Update, rotate_arrows.in_set(GameplaySet));
+
+        match self.backend {
+            PhysicsBackend::Builtin => {
+                app.add_systems(
+                    FixedUpdate,
+                    (
+                        (apply_velocity.after(fire_shots), apply_gravity)
+                            .chain()
+                            .in_set(GameplaySet),
+                        // Bounces change the velocity too, so gravity has to be applied first
+                        check_for_collisions
+                            .after(apply_gravity)
+                            .in_set(CollisionSet),
+                    ),
+                );
+            }
+            PhysicsBackend::Rapier => {
+                app.add_plugins(RapierBackendPlugin);
+            }
+        }
+    }
+}
+
+/// What moves the arrows and detects their collisions.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PhysicsBackend {
+    /// Simple motion and axis aligned collisions.
+    #[default]
+    Builtin,
+    /// Rigid bodies simulated by rapier, which also handles rotated and spinning obstacles.
+    Rapier,
+}
+
+impl PhysicsBackend {
+    /// The backend requested with the `--physics <builtin|rapier>` command-line argument, if any.
+    pub fn from_environment() -> Option<PhysicsBackend> {
+        crate::args::option("physics").and_then(|value| Self::parse(&value))
+    }
+
+    fn parse(value: &str) -> Option<PhysicsBackend> {
+        match value {
+            "builtin" => Some(PhysicsBackend::Builtin),
+            "rapier" => Some(PhysicsBackend::Rapier),
+            _ => {
+                warn!("Unknown physics backend {value}");
+                None
+            }
+        }
+    }
+}
+
+/// The systems detecting collisions of arrows, popping balloons and stopping arrows at walls.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CollisionSet;
+
+/// Applies what the [`CollisionSet`] did, like despawning popped balloons and spent arrows,
+/// so that the systems after it don't see them anymore.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CollisionFlushSet;
+
+/// The number of fixed timesteps that have been simulated so far.
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u64);
+
+/// An arrow about to be launched.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Shot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Shots waiting to be fired on the next fixed timestep.
+///
+/// Input is handled every frame, but arrows are only launched from the fixed timestep schedule,
+/// so that the simulation does not depend on the frame rate.
+#[derive(Resource, Default)]
+pub struct ShotQueue(pub Vec<Shot>);
+
+/// Sent when an arrow has been launched.
+#[derive(Event)]
+pub struct ShotFiredEvent {
+    pub arrow: Entity,
+    pub shot: Shot,
+}
+
+/// Why an arrow is done.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShotEnd {
+    LeftArena,
+    Expired,
+    /// The arrow is stuck in a wall, where it stays but no longer counts as an arrow.
+    Stuck,
+    /// The arrow was destroyed by a wall.
+    Destroyed,
+    /// The arrow popped as many balloons as its [`Pierce`] allows.
+    Spent,
+}
+
+/// Sent when an arrow is done, after which it can no longer pop any balloons.
+#[derive(Event)]
+pub struct ShotFinishedEvent {
+    pub arrow: Entity,
+    pub reason: ShotEnd,
+}
+
+#[derive(Component)]
+pub struct Arrow;
+
+/// The number of fixed timesteps left until the arrow is removed.
+#[derive(Component)]
+pub struct Lifetime(pub u32);
+
+#[derive(Component)]
+pub struct Falling;
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+/// How many more balloons an arrow can pop. Arrows without it can pop any number of balloons.
+#[derive(Component)]
+pub struct Pierce(pub u32);
+
+/// Where an entity was before it moved during the last timestep.
+#[derive(Component)]
+pub struct PreviousPosition(pub Vec2);
+
+#[derive(Component)]
+pub struct Collider;
+
+/// The balloons an arrow has hit, which it can't hit again.
+#[derive(Component, Default)]
+pub struct HitBalloons(pub Vec<Entity>);
+
+/// Sent when an arrow hits a balloon, which then pops or loses a layer depending on its kind.
+#[derive(Event)]
+pub struct BalloonHitEvent {
+    pub balloon: Entity,
+    pub arrow: Entity,
+}
+
+/// Sent when a balloon, or one layer of it, pops.
+#[derive(Event)]
+pub struct BalloonPopEvent {
+    pub balloon: Entity,
+    /// The arrow that popped the balloon, or that set off the explosion that did.
+    pub arrow: Entity,
+    /// Where the balloon was when it popped.
+    pub position: Vec2,
+    /// The kind of the balloon before it popped.
+    pub kind: BalloonKind,
+    /// The points the balloon is worth, before any combo bonus.
+    pub points: u32,
+}
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+pub fn fire_shots(
+    mut commands: Commands,
+    mut queue: ResMut<ShotQueue>,
+    textures: Res<Textures>,
+    backend: Res<PhysicsBackend>,
+    mut fired_events: EventWriter<ShotFiredEvent>,
+) {
+    for shot in queue.0.drain(..) {
+        let mut arrow = commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(1.0, 1.0)),
+                    ..Default::default()
+                },
+                texture: textures.arrow.clone(),
+                transform: Transform::from_translation(shot.position.extend(0.0))
+                    .with_rotation(arrow_rotation(shot.velocity))
+                    .with_scale(Vec3::new(ARROW_SCALE, ARROW_SCALE, 1.0)),
+                ..default()
+            },
+            Arrow,
+            Lifetime(ARROW_LIFETIME),
+            Velocity(shot.velocity),
+            PreviousPosition(shot.position),
+            HitBalloons::default(),
+            Falling,
+            LevelEntity,
+        ));
+        if *backend == PhysicsBackend::Rapier {
+            arrow.insert(rapier::arrow_body(shot.velocity));
+        }
+
+        fired_events.send(ShotFiredEvent {
+            arrow: arrow.id(),
+            shot,
+        });
+    }
+}
+
+fn rotate_arrows(mut query: Query<(&mut Transform, &Velocity), With<Arrow>>) {
+    for (mut arrow_transform, arrow_velocity) in &mut query {
+        arrow_transform.rotation = arrow_rotation(arrow_velocity.0);
+    }
+}
+
+/// The rotation of an arrow flying with `velocity`, whose sprite points diagonally up and right.
+fn arrow_rotation(velocity: Vec2) -> Quat {
+    let angle = velocity.y.atan2(velocity.x) - PI / 4.0;
+    Quat::from_axis_angle(Vec3::Z, angle)
+}
+
+/// The positions an arrow fired with `shot` will have after each of the next timesteps,
+/// as long as it doesn't hit anything.
+///
+/// This uses the same integration as [`apply_velocity`] and [`apply_gravity`],
+/// so it matches the flight of the arrow exactly with the builtin backend.
+pub fn predict_trajectory(shot: Shot, time_step: &FixedTime) -> impl Iterator<Item = Vec2> {
+    let dt = time_step.period.as_secs_f32();
+    let mut position = shot.position;
+    let mut velocity = shot.velocity;
+    std::iter::repeat_with(move || {
+        position += velocity * dt;
+        velocity.y -= GRAVITY * dt;
+        position
+    })
+    .take(ARROW_LIFETIME as usize)
+}
+
+pub fn apply_velocity(
+    mut query: Query<(&mut Transform, &Velocity, Option<&mut PreviousPosition>)>,
+    time_step: Res<FixedTime>,
+) {
+    for (mut transform, velocity, previous_position) in &mut query {
+        if let Some(mut previous_position) = previous_position {
+            previous_position.0 = transform.translation.truncate();
+        }
+        transform.translation.x += velocity.x * time_step.period.as_secs_f32();
+        transform.translation.y += velocity.y * time_step.period.as_secs_f32();
+    }
+}
+
+pub fn apply_gravity(mut query: Query<&mut Velocity, With<Falling>>, time_step: Res<FixedTime>) {
+    for mut velocity in &mut query {
+        velocity.y -= GRAVITY * time_step.period.as_secs_f32();
+    }
+}
+
+/// What an arrow hits during a timestep.
+enum Hit {
+    Balloon,
+    /// A metal balloon centered at the given position, which the arrow bounces off.
+    Metal(Vec2),
+    Wall(WallBehaviour, Collision),
+}
+
+#[allow(clippy::type_complexity)]
+pub fn check_for_collisions(
+    mut commands: Commands,
+    mut arrow_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &PreviousPosition,
+            &mut HitBalloons,
+            Option<&mut Pierce>,
+        ),
+        With<Arrow>,
+    >,
+    collider_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&BalloonKind>,
+            Option<&WallBehaviour>,
+        ),
+        (With<Collider>, Without<Arrow>),
+    >,
+    mut hit_events: EventWriter<BalloonHitEvent>,
+    mut finished_events: EventWriter<ShotFinishedEvent>,
+) {
+    // Balloons can only be hit once per timestep, even if several arrows hit them at the same time
+    let mut hit_this_step = Vec::new();
+
+    for (
+        arrow,
+        mut arrow_transform,
+        mut arrow_velocity,
+        previous_position,
+        mut hit_balloons,
+        mut pierce,
+    ) in &mut arrow_query
+    {
+        // Sweep the arrow along its path since the last timestep, so that it can't pass through
+        // anything without touching it, no matter how fast it is
+        let start = previous_position.0;
+        let end = arrow_transform.translation.truncate();
+        let motion = end - start;
+
+        // The arrow is a capsule pointing in the direction it flies
+        let (tail, tip) = arrow_segment(end, arrow_velocity.0);
+        let arrow_size = (tip - tail).abs() + 2.0 * ARROW_RADIUS;
+        let start_tip = tip - motion;
+
+        let mut hits = Vec::new();
+        for (collider_entity, transform, balloon_kind, wall_behaviour) in &collider_query {
+            let center = transform.translation.truncate();
+            let size = transform.scale.truncate();
+
+            if let Some(balloon_kind) = balloon_kind {
+                // The tip pops the balloon on its way, or the shaft touches it at the end
+                let radius = size.min_element() / 2.0 + ARROW_RADIUS;
+                let time = sweep_circle(start_tip, motion, center, radius)
+                    .or_else(|| (distance_to_segment(center, tail, tip) <= radius).then_some(1.0));
+                let hit = match balloon_kind {
+                    BalloonKind::Metal => Hit::Metal(center),
+                    _ => Hit::Balloon,
+                };
+                if let Some(time) = time {
+                    hits.push((time, collider_entity, hit));
+                }
+            } else if let Some((time, side)) = sweep_box(start, motion, arrow_size, center, size) {
+                let behaviour = wall_behaviour.copied().unwrap_or_default();
+                hits.push((time, collider_entity, Hit::Wall(behaviour, side)));
+            }
+        }
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (time, collider_entity, hit) in hits {
+            let (behaviour, collision) = match hit {
+                Hit::Balloon => {
+                    if hit_this_step.contains(&collider_entity)
+                        || hit_balloons.0.contains(&collider_entity)
+                    {
+                        continue;
+                    }
+                    hit_events.send(BalloonHitEvent {
+                        balloon: collider_entity,
+                        arrow,
+                    });
+                    hit_this_step.push(collider_entity);
+                    hit_balloons.0.push(collider_entity);
+
+                    if !use_pierce(pierce.as_deref_mut()) {
+                        continue;
+                    }
+                    commands.entity(arrow).despawn();
+                    finished_events.send(ShotFinishedEvent {
+                        arrow,
+                        reason: ShotEnd::Spent,
+                    });
+                    break;
+                }
+                Hit::Metal(center) => {
+                    // Bounce off the surface where the tip touches the balloon,
+                    // unless the arrow is already moving away from it
+                    let normal = (start_tip + motion * time - center).normalize_or_zero();
+                    let speed_towards = arrow_velocity.0.dot(normal);
+                    if speed_towards >= 0.0 {
+                        continue;
+                    }
+                    arrow_velocity.0 -= (1.0 + METAL_RESTITUTION) * speed_towards * normal;
+
+                    let hit_position = start + motion * time;
+                    arrow_transform.translation =
+                        hit_position.extend(arrow_transform.translation.z);
+                    break;
+                }
+                Hit::Wall(behaviour, collision) => (behaviour, collision),
+            };
+
+            // check collision with walls, stopping at the first one that was hit
+            let reason = match behaviour {
+                WallBehaviour::Stick => {
+                    commands
+                        .entity(arrow)
+                        .remove::<(Arrow, Velocity, Falling, Lifetime)>();
+                    Some(ShotEnd::Stuck)
+                }
+                WallBehaviour::Destroy => {
+                    commands.entity(arrow).despawn();
+                    Some(ShotEnd::Destroyed)
+                }
+                WallBehaviour::Bounce { restitution } => {
+                    // only reflect the velocity if the arrow is moving into the wall,
+                    // so that it doesn't get stuck bouncing back and forth inside it
+                    let velocity = &mut arrow_velocity.0;
+                    match collision {
+                        Collision::Left if velocity.x > 0.0 => velocity.x *= -restitution,
+                        Collision::Right if velocity.x < 0.0 => velocity.x *= -restitution,
+                        Collision::Top if velocity.y < 0.0 => velocity.y *= -restitution,
+                        Collision::Bottom if velocity.y > 0.0 => velocity.y *= -restitution,
+                        _ => continue,
+                    }
+                    None
+                }
+            };
+
+            // Move the arrow back to where it hit the wall
+            let hit_position = start + motion * time;
+            arrow_transform.translation = hit_position.extend(arrow_transform.translation.z);
+
+            if let Some(reason) = reason {
+                finished_events.send(ShotFinishedEvent { arrow, reason });
+            }
+            break;
+        }
+    }
+}
+
+/// Uses up one pop of an arrow, returning whether it can't pop any more balloons.
+pub fn use_pierce(pierce: Option<&mut Pierce>) -> bool {
+    let Some(pierce) = pierce else {
+        return false;
+    };
+    pierce.0 = pierce.0.saturating_sub(1);
+    pierce.0 == 0
+}
+
+/// When a box of `size` moving from `start` by `motion` first touches another box,
+/// as a fraction of the motion, together with the side of the other box that was hit.
+fn sweep_box(
+    start: Vec2,
+    motion: Vec2,
+    size: Vec2,
+    box_center: Vec2,
+    box_size: Vec2,
+) -> Option<(f32, Collision)> {
+    // Moving a box against another box is the same as moving a point against a bigger box
+    let half_size = (size + box_size) / 2.0;
+    let min = box_center - half_size;
+    let max = box_center + half_size;
+
+    let mut enter = Vec2::splat(f32::NEG_INFINITY);
+    let mut exit = Vec2::splat(f32::INFINITY);
+    for axis in 0..2 {
+        if motion[axis] == 0.0 {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return None;
+            }
+        } else {
+            let t1 = (min[axis] - start[axis]) / motion[axis];
+            let t2 = (max[axis] - start[axis]) / motion[axis];
+            enter[axis] = t1.min(t2);
+            exit[axis] = t1.max(t2);
+        }
+    }
+
+    let time = enter.max_element();
+    if time > exit.min_element() || time > 1.0 || exit.min_element() <= 0.0 {
+        return None;
+    }
+    if time < 0.0 {
+        // Already overlapping at the start
+        let side = collide(start.extend(0.0), size, box_center.extend(0.0), box_size)
+            .unwrap_or(Collision::Inside);
+        return Some((0.0, side));
+    }
+
+    let side = if enter.x > enter.y {
+        if motion.x > 0.0 {
+            Collision::Left
+        } else {
+            Collision::Right
+        }
+    } else if motion.y > 0.0 {
+        Collision::Bottom
+    } else {
+        Collision::Top
+    };
+    Some((time, side))
+}
+
+/// When a point moving from `start` by `motion` first touches a circle, as a fraction of the motion.
+fn sweep_circle(start: Vec2, motion: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - center;
+    let c = offset.length_squared() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+
+    let a = motion.length_squared();
+    let b = 2.0 * offset.dot(motion);
+    let discriminant = b * b - 4.0 * a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&time).then_some(time)
+}
+
+/// The tail and tip of an arrow centered at `position` and flying with `velocity`.
+fn arrow_segment(position: Vec2, velocity: Vec2) -> (Vec2, Vec2) {
+    let half = velocity.normalize_or_zero() * ARROW_LENGTH / 2.0;
+    (position - half, position + half)
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let t = if segment == Vec2::ZERO {
+        0.0
+    } else {
+        ((point - start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+    };
+    point.distance(start + segment * t)
+}
+
+pub fn despawn_finished_arrows(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, &mut Lifetime), With<Arrow>>,
+    arena: Res<Arena>,
+    mut finished_events: EventWriter<ShotFinishedEvent>,
+) {
+    for (arrow, transform, mut lifetime) in &mut query {
+        lifetime.0 = lifetime.0.saturating_sub(1);
+
+        let reason = if !arena.contains(transform.translation.truncate()) {
+            ShotEnd::LeftArena
+        } else if lifetime.0 == 0 {
+            ShotEnd::Expired
+        } else {
+            continue;
+        };
+
+        commands.entity(arrow).despawn();
+        finished_events.send(ShotFinishedEvent { arrow, reason });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweeps_find_the_first_touch() {
+        // Moving 100 pixels to the right per step, starting 100 pixels left of the centers
+        let start = Vec2::new(-100.0, 0.0);
+        let motion = Vec2::new(100.0, 0.0);
+
+        assert_eq!(sweep_circle(start, motion, Vec2::ZERO, 20.0), Some(0.8));
+        let (time, side) =
+            sweep_box(start, motion, Vec2::ONE * 2.0, Vec2::ZERO, Vec2::ONE * 40.0).unwrap();
+        assert_eq!(time, 0.79);
+        assert_eq!(side, Collision::Left);
+    }
+
+    #[test]
+    fn sweeps_find_things_much_smaller_than_the_motion() {
+        let start = Vec2::new(-1000.0, 0.0);
+        let motion = Vec2::new(2000.0, 0.0);
+
+        assert!(sweep_circle(start, motion, Vec2::ZERO, 1.0).is_some());
+        assert!(sweep_box(start, motion, Vec2::ONE, Vec2::ZERO, Vec2::new(1.0, 100.0)).is_some());
+        assert_eq!(sweep_circle(start, motion, Vec2::new(0.0, 2.0), 1.0), None);
+    }
+
+    #[test]
+    fn nearer_things_are_touched_earlier() {
+        let start = Vec2::new(-100.0, 0.0);
+        let motion = Vec2::new(200.0, 0.0);
+
+        let near_circle = sweep_circle(start, motion, Vec2::new(-50.0, 0.0), 10.0).unwrap();
+        let far_circle = sweep_circle(start, motion, Vec2::new(50.0, 0.0), 10.0).unwrap();
+        let (middle_box, _) =
+            sweep_box(start, motion, Vec2::ONE, Vec2::ZERO, Vec2::new(10.0, 10.0)).unwrap();
+        assert!(near_circle < middle_box, "{near_circle} {middle_box}");
+        assert!(middle_box < far_circle, "{middle_box} {far_circle}");
+
+        // Coming from the other side, the order is reversed
+        let start = Vec2::new(100.0, 0.0);
+        let motion = Vec2::new(-200.0, 0.0);
+        let near_circle = sweep_circle(start, motion, Vec2::new(50.0, 0.0), 10.0).unwrap();
+        let (middle_box, side) =
+            sweep_box(start, motion, Vec2::ONE, Vec2::ZERO, Vec2::new(10.0, 10.0)).unwrap();
+        assert!(near_circle < middle_box, "{near_circle} {middle_box}");
+        assert_eq!(side, Collision::Right);
+    }
+
+    #[test]
+    fn sweeps_starting_inside_touch_immediately() {
+        let motion = Vec2::new(100.0, 0.0);
+
+        assert_eq!(
+            sweep_circle(Vec2::ZERO, motion, Vec2::ZERO, 10.0),
+            Some(0.0)
+        );
+        let (time, _) =
+            sweep_box(Vec2::ZERO, motion, Vec2::ONE, Vec2::ZERO, Vec2::ONE * 10.0).unwrap();
+        assert_eq!(time, 0.0);
+    }
+
+    #[test]
+    fn sweeps_ignore_things_out_of_reach() {
+        let start = Vec2::new(-100.0, 0.0);
+        let motion = Vec2::new(50.0, 0.0);
+
+        // Too far ahead
+        assert_eq!(sweep_circle(start, motion, Vec2::ZERO, 10.0), None);
+        assert_eq!(
+            sweep_box(start, motion, Vec2::ONE, Vec2::ZERO, Vec2::ONE * 10.0),
+            None
+        );
+        // Behind
+        assert_eq!(
+            sweep_circle(start, motion, Vec2::new(-200.0, 0.0), 10.0),
+            None
+        );
+        assert_eq!(
+            sweep_box(
+                start,
+                motion,
+                Vec2::ONE,
+                Vec2::new(-200.0, 0.0),
+                Vec2::ONE * 10.0
+            ),
+            None
+        );
+    }
+}
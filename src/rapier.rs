@@ -0,0 +1,266 @@
+//! The rapier physics backend, used instead of the builtin physics when
+//! [`PhysicsBackend::Rapier`](crate::physics::PhysicsBackend::Rapier) is chosen.
+//!
+//! Arrows are dynamic rigid bodies, balloons are sensors (except metal ones, which arrows
+//! bounce off) and walls are fixed colliders (or kinematic ones if they spin).
+//! The links of chains are dynamic bodies joined to each other.
+//! Rapier is stepped once per fixed timestep, so the simulation stays independent of the frame rate.
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier2d::plugin::systems;
+use bevy_rapier2d::prelude::{
+    ActiveEvents, Ccd, CoefficientCombineRule, Collider, ColliderScale, CollisionEvent,
+    CollisionGroups, ExternalImpulse, Group, ImpulseJoint, LockedAxes, NoUserData, PhysicsSet,
+    RapierConfiguration, RapierPhysicsPlugin, ReadMassProperties, Restitution,
+    RevoluteJointBuilder, RigidBody, Sensor, TimestepMode, Velocity,
+};
+
+use crate::arena::WallBehaviour;
+use crate::balloons::{BalloonKind, METAL_RESTITUTION};
+use crate::physics::{self, fire_shots, Arrow, BalloonHitEvent, CollisionSet, GRAVITY};
+use crate::physics::{use_pierce, Falling, Lifetime, Pierce, ShotEnd, ShotFinishedEvent};
+use crate::physics::{ARROW_LENGTH, ARROW_RADIUS};
+use crate::GameplaySet;
+
+/// The collision group of arrows, which pass through each other.
+const ARROW_GROUP: Group = Group::GROUP_1;
+
+/// Colliders are given in pixels instead of being scaled with their sprites. Rapier only
+/// applies the scale of a transform once it changes, so a balloon standing still would never
+/// get its size.
+const UNSCALED: ColliderScale = ColliderScale::Absolute(Vec2::ONE);
+
+pub struct RapierBackendPlugin;
+
+impl Plugin for RapierBackendPlugin {
+    fn build(&self, app: &mut App) {
+        let time_step = app.world.resource::<FixedTime>().period.as_secs_f32();
+
+        app.insert_resource(RapierConfiguration {
+            gravity: Vec2::NEG_Y * GRAVITY,
+            timestep_mode: TimestepMode::Fixed {
+                dt: time_step,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false))
+        .configure_sets(
+            FixedUpdate,
+            (
+                PhysicsSet::SyncBackend,
+                PhysicsSet::SyncBackendFlush,
+                PhysicsSet::StepSimulation,
+                PhysicsSet::Writeback,
+            )
+                .chain()
+                .after(fire_shots)
+                .in_set(GameplaySet),
+        )
+        .configure_set(FixedUpdate, CollisionSet.after(PhysicsSet::Writeback))
+        .add_systems(
+            FixedUpdate,
+            (
+                get_systems(PhysicsSet::SyncBackend).in_set(PhysicsSet::SyncBackend),
+                get_systems(PhysicsSet::SyncBackendFlush).in_set(PhysicsSet::SyncBackendFlush),
+                get_systems(PhysicsSet::StepSimulation).in_set(PhysicsSet::StepSimulation),
+                get_systems(PhysicsSet::Writeback).in_set(PhysicsSet::Writeback),
+                (copy_velocities, handle_collisions).in_set(CollisionSet),
+            ),
+        )
+        // Entities are also despawned while the simulation is not running, for example when
+        // leaving a level, and rapier has to notice that before the removals are forgotten
+        .add_systems(PostUpdate, systems::sync_removals);
+    }
+}
+
+fn get_systems(set: PhysicsSet) -> bevy::ecs::schedule::SystemConfigs {
+    RapierPhysicsPlugin::<NoUserData>::get_systems(set)
+}
+
+/// The rapier components of a newly fired arrow.
+pub fn arrow_body(velocity: Vec2) -> impl Bundle {
+    // The arrow is drawn diagonally across its sprite, which is turned to face the velocity
+    let half = Vec2::ONE.normalize() * ARROW_LENGTH / 2.0;
+    (
+        RigidBody::Dynamic,
+        Collider::capsule(-half, half, ARROW_RADIUS),
+        UNSCALED,
+        Velocity::linear(velocity),
+        // The arrow is turned to face its velocity instead
+        LockedAxes::ROTATION_LOCKED,
+        Restitution::coefficient(0.0),
+        Ccd::enabled(),
+        ReadMassProperties::default(),
+        ActiveEvents::COLLISION_EVENTS,
+        CollisionGroups::new(ARROW_GROUP, Group::ALL - ARROW_GROUP),
+    )
+}
+
+/// Adds the rapier components of a balloon with the given diameter to `balloon`.
+/// Arrows fly through all kinds of balloons except metal ones.
+pub fn insert_balloon_body(balloon: &mut EntityCommands, kind: BalloonKind, size: f32) {
+    balloon.insert((Collider::ball(size / 2.0), UNSCALED));
+    match kind {
+        BalloonKind::Metal => balloon.insert(Restitution {
+            coefficient: METAL_RESTITUTION,
+            combine_rule: CoefficientCombineRule::Max,
+        }),
+        _ => balloon.insert(Sensor),
+    };
+}
+
+/// The rapier components of a wall or an obstacle of the given size,
+/// rotating at `spin` radians per second.
+pub fn wall_body(behaviour: WallBehaviour, size: Vec2, spin: f32) -> impl Bundle {
+    let body = if spin == 0.0 {
+        RigidBody::Fixed
+    } else {
+        RigidBody::KinematicVelocityBased
+    };
+    (
+        body,
+        Velocity::angular(spin),
+        Collider::cuboid(size.x / 2.0, size.y / 2.0),
+        UNSCALED,
+        wall_restitution(behaviour),
+    )
+}
+
+/// The rapier components of the fixed point at `position` that a chain hangs from.
+pub fn chain_anchor_body(position: Vec2) -> impl Bundle {
+    (
+        RigidBody::Fixed,
+        TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+    )
+}
+
+/// The rapier components of a chain link of the given size, hanging from `parent`.
+/// The top of the link is attached at `parent_anchor`, relative to the center of the parent.
+pub fn chain_link_body(
+    behaviour: WallBehaviour,
+    size: Vec2,
+    parent: Entity,
+    parent_anchor: Vec2,
+) -> impl Bundle {
+    let mut joint = RevoluteJointBuilder::new()
+        .local_anchor1(parent_anchor)
+        .local_anchor2(Vec2::new(0.0, size.y / 2.0))
+        .build();
+    // Neighbouring links touch where they are joined, which must not push them apart
+    joint.set_contacts_enabled(false);
+    (
+        RigidBody::Dynamic,
+        Collider::cuboid(size.x / 2.0, size.y / 2.0),
+        UNSCALED,
+        wall_restitution(behaviour),
+        ImpulseJoint::new(parent, joint),
+        ExternalImpulse::default(),
+    )
+}
+
+fn wall_restitution(behaviour: WallBehaviour) -> Restitution {
+    let restitution = match behaviour {
+        WallBehaviour::Bounce { restitution } => restitution,
+        WallBehaviour::Stick | WallBehaviour::Destroy => 0.0,
+    };
+    Restitution {
+        coefficient: restitution,
+        combine_rule: CoefficientCombineRule::Max,
+    }
+}
+
+// Keep the velocity used for turning arrows up to date
+fn copy_velocities(mut query: Query<(&mut physics::Velocity, &Velocity), With<Arrow>>) {
+    for (mut velocity, body_velocity) in &mut query {
+        velocity.0 = body_velocity.linvel;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_collisions(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut arrows: Query<Option<&mut Pierce>, With<Arrow>>,
+    arrow_motion: Query<(&Transform, &Velocity, &ReadMassProperties), With<Arrow>>,
+    balloons: Query<&BalloonKind>,
+    walls: Query<&WallBehaviour>,
+    mut links: Query<(&Transform, &mut ExternalImpulse)>,
+    mut hit_events: EventWriter<BalloonHitEvent>,
+    mut finished_events: EventWriter<ShotFinishedEvent>,
+) {
+    // Arrows and balloons that are done, so they are not handled twice in the same step
+    let mut done = Vec::new();
+
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = *event else {
+            continue;
+        };
+        let (arrow, other) = if arrows.contains(a) { (a, b) } else { (b, a) };
+        if !arrows.contains(arrow) || done.contains(&arrow) || done.contains(&other) {
+            continue;
+        }
+
+        if let Ok(kind) = balloons.get(other) {
+            // Bouncing off metal balloons is handled by rapier itself
+            if *kind == BalloonKind::Metal {
+                continue;
+            }
+            hit_events.send(BalloonHitEvent {
+                balloon: other,
+                arrow,
+            });
+            done.push(other);
+
+            let Ok(pierce) = arrows.get_mut(arrow) else {
+                continue;
+            };
+            if use_pierce(pierce.map(|pierce| pierce.into_inner())) {
+                commands.entity(arrow).despawn();
+                finished_events.send(ShotFinishedEvent {
+                    arrow,
+                    reason: ShotEnd::Spent,
+                });
+                done.push(arrow);
+            }
+            continue;
+        }
+
+        // Bouncing is handled by rapier itself
+        let reason = match walls.get(other) {
+            Ok(WallBehaviour::Stick) if links.contains(other) => {
+                // The link swings away, so instead of staying where it hit, the arrow gives the
+                // link its momentum
+                let (arrow_transform, velocity, mass) = arrow_motion.get(arrow).unwrap();
+                let (link_transform, mut impulse) = links.get_mut(other).unwrap();
+                *impulse += ExternalImpulse::at_point(
+                    velocity.linvel * mass.0.mass,
+                    arrow_transform.translation.truncate(),
+                    link_transform.translation.truncate(),
+                );
+                commands.entity(arrow).despawn();
+                ShotEnd::Stuck
+            }
+            Ok(WallBehaviour::Stick) => {
+                commands.entity(arrow).remove::<(
+                    Arrow,
+                    physics::Velocity,
+                    Falling,
+                    Lifetime,
+                    RigidBody,
+                    Collider,
+                    Velocity,
+                )>();
+                ShotEnd::Stuck
+            }
+            Ok(WallBehaviour::Destroy) => {
+                commands.entity(arrow).despawn();
+                ShotEnd::Destroyed
+            }
+            Ok(WallBehaviour::Bounce { .. }) | Err(_) => continue,
+        };
+
+        finished_events.send(ShotFinishedEvent { arrow, reason });
+        done.push(arrow);
+    }
+}
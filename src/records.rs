@@ -0,0 +1,102 @@
+//! The best results of each level, which are kept between sessions.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ammo::Ammo;
+use crate::level::{CurrentLevel, LevelTick, Levels};
+use crate::replay::ReplayPlayback;
+use crate::scoring::{award_completion_bonus, Scoreboard};
+use crate::{storage, GameState};
+
+/// The key the records are stored under between sessions.
+const RECORDS_KEY: &str = "records";
+
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Records>(RECORDS_KEY).unwrap_or_default())
+            .init_resource::<NewRecords>()
+            .add_systems(
+                OnEnter(GameState::LevelComplete),
+                // Watching a replay doesn't set records
+                update_records
+                    .after(award_completion_bonus)
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+            );
+    }
+}
+
+/// The best results of a level. Each of them may come from a different attempt.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct LevelRecord {
+    pub best_score: usize,
+    pub fewest_arrows: u32,
+    /// In seconds.
+    pub fastest_time: f32,
+}
+
+/// The records of every level that has been completed, by the [name](crate::level::Level::name)
+/// of the level.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Records(pub BTreeMap<String, LevelRecord>);
+
+/// Which records were broken when the current level was completed.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct NewRecords {
+    pub score: bool,
+    pub arrows: bool,
+    pub time: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_records(
+    mut records: ResMut<Records>,
+    mut new_records: ResMut<NewRecords>,
+    scoreboard: Res<Scoreboard>,
+    ammo: Res<Ammo>,
+    tick: Res<LevelTick>,
+    time_step: Res<FixedTime>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+) {
+    let name = &levels.0[current_level.index].name;
+    if name.is_empty() {
+        *new_records = default();
+        return;
+    }
+
+    let result = LevelRecord {
+        best_score: scoreboard.level_score,
+        fewest_arrows: ammo.used,
+        fastest_time: tick.0 as f32 * time_step.period.as_secs_f32(),
+    };
+
+    *new_records = match records.0.get_mut(name) {
+        Some(record) => {
+            let new_records = NewRecords {
+                score: result.best_score > record.best_score,
+                arrows: result.fewest_arrows < record.fewest_arrows,
+                time: result.fastest_time < record.fastest_time,
+            };
+            record.best_score = record.best_score.max(result.best_score);
+            record.fewest_arrows = record.fewest_arrows.min(result.fewest_arrows);
+            record.fastest_time = record.fastest_time.min(result.fastest_time);
+            new_records
+        }
+        // The first time a level is completed everything is a record
+        None => {
+            records.0.insert(name.clone(), result);
+            NewRecords {
+                score: true,
+                arrows: true,
+                time: true,
+            }
+        }
+    };
+
+    storage::save(RECORDS_KEY, &*records);
+}
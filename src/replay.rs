@@ -0,0 +1,217 @@
+//! Recording the shots of a game, and playing them back.
+//!
+//! Together with the seed, the shots and the ticks they were fired on are enough
+//! to reproduce a game exactly, since the rest of the simulation is deterministic.
+
+use std::{fs, io, path::Path, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier2d::prelude::PhysicsSet;
+use serde::{Deserialize, Serialize};
+
+use crate::level::{advance_level_tick, CurrentLevel, LevelTick, RetryLevelEvent};
+use crate::physics::{fire_shots, CollisionSet, PhysicsBackend, Shot, ShotFiredEvent, ShotQueue};
+use crate::{GameState, GameplaySet, Seed};
+
+/// Records every shot into the [`Replay`] resource, and optionally plays back an earlier replay.
+pub struct ReplayPlugin {
+    /// The replay to play back instead of taking input from the mouse.
+    pub playback: Option<Replay>,
+    /// Where to save the recorded replay when the app exits.
+    pub save_path: Option<PathBuf>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_recording)
+            .add_systems(
+                PreUpdate,
+                record_start_level.run_if(
+                    resource_added::<CurrentLevel>().and_then(resource_exists::<Recording>()),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_shots
+                    .after(fire_shots)
+                    .before(PhysicsSet::SyncBackendFlush)
+                    .before(CollisionSet)
+                    .in_set(GameplaySet)
+                    .run_if(resource_exists::<Recording>()),
+            )
+            // Only the first game played is recorded
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                stop_recording.run_if(resource_exists::<CurrentLevel>()),
+            );
+
+        if let Some(replay) = &self.playback {
+            app.insert_resource(ReplayPlayback {
+                start_level: replay.start_level,
+                shots: replay.shots.clone(),
+                next: 0,
+            })
+            .add_systems(
+                FixedUpdate,
+                play_back_shots
+                    .after(advance_level_tick)
+                    .before(fire_shots)
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                Update,
+                retry_during_playback.run_if(in_state(GameState::GameOver)),
+            );
+        }
+
+        if let Some(path) = &self.save_path {
+            app.insert_resource(ReplaySavePath(path.clone()))
+                .add_systems(Last, save_replay_on_exit);
+        }
+    }
+}
+
+/// A shot together with the level attempt and the tick within it that it was fired on.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct RecordedShot {
+    pub level: usize,
+    pub attempt: u32,
+    pub tick: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Everything needed to reproduce a game.
+///
+/// While the game is running, the shots fired so far are recorded in this resource.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    /// The backends do not give exactly the same results, so the replay has to use the same one.
+    #[serde(default)]
+    pub physics: PhysicsBackend,
+    /// The level the game was started from.
+    #[serde(default)]
+    pub start_level: usize,
+    pub shots: Vec<RecordedShot>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, text)
+    }
+}
+
+/// The shots of a replay being played back.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    start_level: usize,
+    shots: Vec<RecordedShot>,
+    next: usize,
+}
+
+impl ReplayPlayback {
+    /// The level the replayed game was started from.
+    pub fn start_level(&self) -> usize {
+        self.start_level
+    }
+
+    /// Whether all shots of the replay have been fired.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.shots.len()
+    }
+}
+
+#[derive(Resource)]
+struct ReplaySavePath(PathBuf);
+
+/// Exists while the shots fired are added to the [`Replay`].
+#[derive(Resource)]
+struct Recording;
+
+fn start_recording(mut commands: Commands, seed: Res<Seed>, physics: Res<PhysicsBackend>) {
+    commands.insert_resource(Replay {
+        seed: seed.0,
+        physics: *physics,
+        start_level: 0,
+        shots: Vec::new(),
+    });
+    commands.insert_resource(Recording);
+}
+
+fn stop_recording(mut commands: Commands) {
+    commands.remove_resource::<Recording>();
+}
+
+fn record_start_level(mut replay: ResMut<Replay>, level: Res<CurrentLevel>) {
+    replay.start_level = level.index;
+}
+
+fn record_shots(
+    mut replay: ResMut<Replay>,
+    mut fired_events: EventReader<ShotFiredEvent>,
+    level: Res<CurrentLevel>,
+    tick: Res<LevelTick>,
+) {
+    for event in fired_events.iter() {
+        replay.shots.push(RecordedShot {
+            level: level.index,
+            attempt: level.attempt,
+            tick: tick.0,
+            position: event.shot.position,
+            velocity: event.shot.velocity,
+        });
+    }
+}
+
+fn play_back_shots(
+    mut playback: ResMut<ReplayPlayback>,
+    level: Res<CurrentLevel>,
+    tick: Res<LevelTick>,
+    mut queue: ResMut<ShotQueue>,
+) {
+    while let Some(recorded) = playback.shots.get(playback.next) {
+        if (recorded.level, recorded.attempt, recorded.tick) > (level.index, level.attempt, tick.0)
+        {
+            break;
+        }
+        queue.0.push(Shot {
+            position: recorded.position,
+            velocity: recorded.velocity,
+        });
+        playback.next += 1;
+    }
+}
+
+// The player retried the level if the replay has more shots for it
+fn retry_during_playback(
+    playback: Res<ReplayPlayback>,
+    level: Res<CurrentLevel>,
+    mut retry_events: EventWriter<RetryLevelEvent>,
+) {
+    if let Some(recorded) = playback.shots.get(playback.next) {
+        if recorded.level == level.index && recorded.attempt > level.attempt {
+            retry_events.send_default();
+        }
+    }
+}
+
+fn save_replay_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    replay: Res<Replay>,
+    path: Res<ReplaySavePath>,
+) {
+    if exit_events.iter().next().is_some() {
+        match replay.save(&path.0) {
+            Ok(()) => info!("Saved replay to {}", path.0.display()),
+            Err(err) => error!("Could not save replay to {}: {err}", path.0.display()),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Keeping track of the score.
+//!
+//! Every balloon is worth some points, which grow when one arrow pops several balloons in a row.
+//! Completing a level gives bonus points for the arrows left and for being quick.
+//! How many points are given is decided by the [`ScoringRules`] of each level.
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::ammo::Ammo;
+use crate::level::{CurrentLevel, LevelTick, Levels};
+use crate::physics::{BalloonPopEvent, CollisionFlushSet, ShotFiredEvent};
+use crate::GameState;
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scoreboard {
+            score: 0,
+            level_score: 0,
+            breakdown: default(),
+            combo: 0,
+            best_combo: 0,
+        })
+        .init_resource::<Combos>()
+        .add_event::<PointsScoredEvent>()
+        .add_systems(
+            PreUpdate,
+            reset_level_score.run_if(resource_exists_and_changed::<CurrentLevel>()),
+        )
+        .add_systems(FixedUpdate, count_pops.after(CollisionFlushSet))
+        .add_systems(OnEnter(GameState::LevelComplete), award_completion_bonus);
+    }
+}
+
+/// How points are given in a level. Rules left out of a level file get their default values.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct ScoringRules {
+    /// How much the points of a balloon are multiplied by for every balloon popped before it
+    /// by the same arrow, on top of the normal points.
+    pub combo_step: f32,
+    /// The highest multiplier a combo can reach.
+    pub max_multiplier: f32,
+    /// Points for every arrow left when the level is complete.
+    pub arrow_bonus: u32,
+    /// Points for completing the level instantly, shrinking to nothing at `time_limit`.
+    pub time_bonus: u32,
+    /// In seconds.
+    pub time_limit: f32,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            combo_step: 0.5,
+            max_multiplier: 4.0,
+            arrow_bonus: 5,
+            time_bonus: 20,
+            time_limit: 60.0,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// The points for a balloon worth `points`, popped as number `combo` by the same arrow.
+    pub fn combo_points(&self, points: u32, combo: u32) -> u32 {
+        let multiplier = 1.0 + combo.saturating_sub(1) as f32 * self.combo_step;
+        (points as f32 * multiplier.min(self.max_multiplier)).round() as u32
+    }
+
+    /// The bonus for completing a level after `time` seconds.
+    pub fn time_points(&self, time: f32) -> u32 {
+        if self.time_limit <= 0.0 {
+            return 0;
+        }
+        let left = (1.0 - time / self.time_limit).max(0.0);
+        (self.time_bonus as f32 * left).round() as u32
+    }
+}
+
+/// Where the points of the current level came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ScoreBreakdown {
+    /// The points of the popped balloons.
+    pub balloons: usize,
+    /// The extra points from popping several balloons with one arrow.
+    pub combos: usize,
+    /// The bonus for arrows left at the end.
+    pub arrows: usize,
+    /// The bonus for completing the level quickly.
+    pub time: usize,
+}
+
+// This resource tracks the game's score
+#[derive(Resource)]
+pub struct Scoreboard {
+    /// The score over all levels played so far.
+    pub score: usize,
+    /// The score in the current level.
+    pub level_score: usize,
+    pub breakdown: ScoreBreakdown,
+    /// The number of balloons popped by the latest arrow to pop one, until the next shot.
+    pub combo: u32,
+    /// The most balloons popped by one arrow in the current level.
+    pub best_combo: u32,
+}
+
+impl Scoreboard {
+    fn add(&mut self, points: usize) {
+        self.score += points;
+        self.level_score += points;
+    }
+}
+
+/// Sent when points are scored for a popped balloon, including any combo bonus.
+#[derive(Event)]
+pub struct PointsScoredEvent {
+    /// Where the balloon was.
+    pub position: Vec2,
+    pub points: u32,
+    /// How many balloons the arrow has popped, including this one.
+    pub combo: u32,
+}
+
+/// The number of balloons each arrow popped in the current level.
+#[derive(Resource, Default)]
+pub struct Combos(HashMap<Entity, u32>);
+
+fn reset_level_score(
+    mut scoreboard: ResMut<Scoreboard>,
+    mut combos: ResMut<Combos>,
+    current_level: Res<CurrentLevel>,
+) {
+    // Points from a failed attempt don't count
+    if current_level.is_added() {
+        scoreboard.score = 0;
+    } else if current_level.attempt > 0 {
+        scoreboard.score -= scoreboard.level_score;
+    }
+    scoreboard.level_score = 0;
+    scoreboard.breakdown = default();
+    scoreboard.combo = 0;
+    scoreboard.best_combo = 0;
+    combos.0.clear();
+}
+
+pub fn count_pops(
+    mut scoreboard: ResMut<Scoreboard>,
+    mut combos: ResMut<Combos>,
+    mut pop_events: EventReader<BalloonPopEvent>,
+    mut fired_events: EventReader<ShotFiredEvent>,
+    mut scored_events: EventWriter<PointsScoredEvent>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+) {
+    // A new shot ends the combo shown, but the arrows in flight can still add to their own
+    if fired_events.iter().next().is_some() {
+        scoreboard.combo = 0;
+    }
+
+    let rules = &levels.0[current_level.index].scoring;
+    for event in pop_events.iter() {
+        let combo = combos.0.entry(event.arrow).or_default();
+        *combo += 1;
+        let combo = *combo;
+
+        let points = rules.combo_points(event.points, combo);
+        scoreboard.breakdown.balloons += event.points as usize;
+        scoreboard.breakdown.combos += points.saturating_sub(event.points) as usize;
+        scoreboard.add(points as usize);
+        scoreboard.combo = combo;
+        scoreboard.best_combo = scoreboard.best_combo.max(combo);
+
+        scored_events.send(PointsScoredEvent {
+            position: event.position,
+            points,
+            combo,
+        });
+    }
+}
+
+pub fn award_completion_bonus(
+    mut scoreboard: ResMut<Scoreboard>,
+    ammo: Res<Ammo>,
+    tick: Res<LevelTick>,
+    time_step: Res<FixedTime>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+) {
+    let rules = &levels.0[current_level.index].scoring;
+    let time = tick.0 as f32 * time_step.period.as_secs_f32();
+
+    let arrows = (ammo.arrows * rules.arrow_bonus) as usize;
+    let time = rules.time_points(time) as usize;
+    scoreboard.breakdown.arrows = arrows;
+    scoreboard.breakdown.time = time;
+    scoreboard.add(arrows + time);
+}
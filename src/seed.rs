@@ -0,0 +1,41 @@
+//! Choosing the seed for the random number generator, so that a game can be reproduced.
+
+use bevy::prelude::*;
+use rand_core::{OsRng, RngCore};
+
+/// The seed the random number generators, like the one placing the balloons of each level, are
+/// created from.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    /// A seed taken from the operating system's entropy source.
+    pub fn random() -> Seed {
+        Seed(OsRng.next_u64())
+    }
+
+    /// The seed requested when the game was started, if any.
+    ///
+    /// Natively this is the `--seed <n>` command-line argument,
+    /// and on the web it is the `seed` query parameter of the page URL.
+    pub fn from_environment() -> Option<Seed> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            crate::args::option("seed").and_then(|value| Self::parse(&value))
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let search = web_sys::window()?.location().search().ok()?;
+            search
+                .trim_start_matches('?')
+                .split('&')
+                .find_map(|param| param.strip_prefix("seed="))
+                .and_then(Self::parse)
+        }
+    }
+
+    fn parse(value: &str) -> Option<Seed> {
+        value.parse().ok().map(Seed)
+    }
+}
@@ -0,0 +1,68 @@
+//! Data that is kept between sessions, like settings.
+//!
+//! Natively every key is stored as a RON file in the `save` directory,
+//! and on the web it is stored as RON in the browser's `localStorage`.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIRECTORY: &str = "save";
+
+/// The value stored under `key`, if there is one and it can be read.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let text = read(key)?;
+    match ron::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring saved {key}, which could not be read: {err}");
+            None
+        }
+    }
+}
+
+/// Stores `value` under `key`, replacing what was there before.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, default()) {
+        Ok(text) => write(key, &text),
+        Err(err) => warn!("Could not save {key}: {err}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(SAVE_DIRECTORY).join(format!("{key}.ron"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, text: &str) {
+    let result =
+        std::fs::create_dir_all(SAVE_DIRECTORY).and_then(|()| std::fs::write(path(key), text));
+    if let Err(err) = result {
+        warn!("Could not save {key}: {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("bloons.{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, text: &str) {
+    let saved =
+        local_storage().and_then(|storage| storage.set_item(&format!("bloons.{key}"), text).ok());
+    if saved.is_none() {
+        warn!("Could not save {key} to local storage");
+    }
+}
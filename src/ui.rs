@@ -0,0 +1,306 @@
+//! The HUD showing the score and arrows, the results screens, and other presentation details.
+
+use bevy::{prelude::*, render::texture::ImageSampler};
+
+use crate::ammo::Ammo;
+use crate::level::{CurrentLevel, Levels};
+use crate::records::{update_records, NewRecords, Records};
+use crate::scoring::{award_completion_bonus, Scoreboard};
+use crate::{GameState, Seed};
+
+const SCOREBOARD_FONT_SIZE: f32 = 40.0;
+const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
+const SEED_FONT_SIZE: f32 = 20.0;
+const RESULTS_TITLE_FONT_SIZE: f32 = 60.0;
+const RESULTS_FONT_SIZE: f32 = 30.0;
+
+const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
+const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+const COMBO_COLOR: Color = Color::rgb(1.0, 0.6, 0.1);
+const RESULTS_BACKGROUND_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (spawn_camera, spawn_scoreboard, spawn_seed_text))
+            .add_systems(Update, (spritemap_fix, update_scoreboard))
+            .add_systems(
+                OnEnter(GameState::LevelComplete),
+                spawn_results_screen
+                    .after(award_completion_bonus)
+                    .after(update_records),
+            )
+            .add_systems(OnExit(GameState::LevelComplete), despawn_overlay)
+            .add_systems(OnEnter(GameState::GameOver), spawn_game_over_screen)
+            .add_systems(OnExit(GameState::GameOver), despawn_overlay)
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_overlay)
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_screen)
+            .add_systems(OnExit(GameState::Paused), despawn_overlay);
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+fn spawn_scoreboard(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Score: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: SCORE_COLOR,
+                ..default()
+            }),
+            TextSection::new(
+                "  Arrows: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: SCORE_COLOR,
+                ..default()
+            }),
+            // The combo is only shown while there is one
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: COMBO_COLOR,
+                ..default()
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: SCOREBOARD_TEXT_PADDING,
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        }),
+        ScoreText,
+    ));
+}
+
+// Showing the seed lets players share and replay the exact same layout
+fn spawn_seed_text(mut commands: Commands, seed: Res<Seed>) {
+    commands.spawn(
+        TextBundle::from_section(
+            format!("Seed: {}", seed.0),
+            TextStyle {
+                font_size: SEED_FONT_SIZE,
+                color: TEXT_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: SCOREBOARD_TEXT_PADDING,
+            right: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        }),
+    );
+}
+
+/// A screen shown on top of the level, e.g. with the results of the level.
+#[derive(Component)]
+struct Overlay;
+
+fn spawn_overlay(commands: &mut Commands, title: String, lines: Vec<String>) {
+    let text_style = TextStyle {
+        font_size: RESULTS_FONT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: RESULTS_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            Overlay,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: RESULTS_TITLE_FONT_SIZE,
+                    color: SCORE_COLOR,
+                    ..default()
+                },
+            ));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(line, text_style.clone()));
+            }
+        });
+}
+
+fn despawn_overlay(mut commands: Commands, query: Query<Entity, With<Overlay>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_results_screen(
+    mut commands: Commands,
+    scoreboard: Res<Scoreboard>,
+    // The records are only there when the `RecordsPlugin` is added
+    records: Option<Res<Records>>,
+    new_records: Option<Res<NewRecords>>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+) {
+    let is_last_level = current_level.index + 1 == levels.0.len();
+    let breakdown = &scoreboard.breakdown;
+    let level = &levels.0[current_level.index];
+    let new_records = new_records.map_or_else(default, |new_records| *new_records);
+    let record_lines = records
+        .as_ref()
+        .and_then(|records| records.0.get(&level.name))
+        .map(|record| {
+            let new = |is_new: bool| if is_new { " - New record!" } else { "" };
+            [
+                format!(
+                    "Best score: {}{}",
+                    record.best_score,
+                    new(new_records.score)
+                ),
+                format!(
+                    "Fewest arrows: {}{}",
+                    record.fewest_arrows,
+                    new(new_records.arrows)
+                ),
+                format!(
+                    "Fastest time: {:.1} s{}",
+                    record.fastest_time,
+                    new(new_records.time)
+                ),
+            ]
+        });
+
+    spawn_overlay(
+        &mut commands,
+        format!("Level {} complete!", current_level.index + 1),
+        vec![
+            format!("Balloons: {}", breakdown.balloons),
+            format!(
+                "Combos: +{} (best x{})",
+                breakdown.combos, scoreboard.best_combo
+            ),
+            format!("Arrows left: +{}", breakdown.arrows),
+            format!("Time bonus: +{}", breakdown.time),
+            format!("Level score: {}", scoreboard.level_score),
+            format!("Total score: {}", scoreboard.score),
+        ]
+        .into_iter()
+        .chain(record_lines.into_iter().flatten())
+        .chain([if is_last_level {
+            "All levels complete! Click to return to the main menu".to_string()
+        } else {
+            "Get ready for the next level...".to_string()
+        }])
+        .collect(),
+    );
+}
+
+fn spawn_game_over_screen(mut commands: Commands) {
+    spawn_overlay(
+        &mut commands,
+        "Out of arrows!".to_string(),
+        vec![
+            "Click or press R to try again".to_string(),
+            "Press M for the main menu".to_string(),
+        ],
+    );
+}
+
+// The main menu is also where a level is chosen, so it shows the records of each level
+fn spawn_main_menu(mut commands: Commands, levels: Res<Levels>, records: Option<Res<Records>>) {
+    let level_lines = levels.0.iter().enumerate().map(|(index, level)| {
+        let title = if level.name.is_empty() {
+            format!("Level {}", index + 1)
+        } else {
+            format!("Level {} ({})", index + 1, level.name)
+        };
+        let record = records
+            .as_ref()
+            .and_then(|records| records.0.get(&level.name));
+        match record {
+            Some(record) => format!(
+                "{title}: best score {}, {} arrows, {:.1} s",
+                record.best_score, record.fewest_arrows, record.fastest_time
+            ),
+            None => format!("{title}: not completed yet"),
+        }
+    });
+
+    spawn_overlay(
+        &mut commands,
+        "Bloons".to_string(),
+        level_lines
+            .chain([
+                "Click or press Enter to play".to_string(),
+                format!("Press 1-{} to start from a level", levels.0.len().min(9)),
+                "Press Esc to quit".to_string(),
+            ])
+            .collect(),
+    );
+}
+
+fn spawn_pause_screen(mut commands: Commands) {
+    spawn_overlay(
+        &mut commands,
+        "Paused".to_string(),
+        vec![
+            "Press P or Esc to resume".to_string(),
+            "Press M for the main menu".to_string(),
+        ],
+    );
+}
+
+fn spritemap_fix(mut ev_asset: EventReader<AssetEvent<Image>>, mut assets: ResMut<Assets<Image>>) {
+    for ev in ev_asset.iter() {
+        if let AssetEvent::Created { handle } = ev {
+            if let Some(texture) = assets.get_mut(handle) {
+                texture.sampler_descriptor = ImageSampler::nearest()
+            }
+        }
+    }
+}
+
+fn update_scoreboard(
+    scoreboard: Res<Scoreboard>,
+    ammo: Res<Ammo>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    let mut text = query.single_mut();
+    text.sections[1].value = scoreboard.score.to_string();
+    text.sections[3].value = ammo.arrows.to_string();
+    text.sections[4].value = if scoreboard.combo > 1 {
+        format!("  Combo x{}", scoreboard.combo)
+    } else {
+        String::new()
+    };
+}
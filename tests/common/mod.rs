@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests, which all run the game headless.
+
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use bloons::ammo::Ammo;
+use bloons::level::{Balloon, Level, RetryLevelEvent};
+use bloons::physics::{Arrow, Shot, ShotQueue};
+use bloons::{headless, BloonsPlugin, GameState};
+
+/// A headless app playing `plugin`, updated until the first level is being played.
+pub fn start(plugin: BloonsPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, plugin));
+    while state(&app) != GameState::Playing {
+        app.update();
+    }
+    app
+}
+
+/// A headless app playing only the level described by `ron`.
+pub fn start_level(ron: &str) -> App {
+    let level = Level::from_ron(ron).expect("the test level should be valid");
+    start(BloonsPlugin::headless().with_level(level))
+}
+
+pub fn state(app: &App) -> GameState {
+    *app.world.resource::<State<GameState>>().get()
+}
+
+pub fn shoot(app: &mut App, position: Vec2, velocity: Vec2) {
+    app.world
+        .resource_mut::<ShotQueue>()
+        .0
+        .push(Shot { position, velocity });
+}
+
+pub fn balloon_positions(app: &mut App) -> Vec<Vec2> {
+    app.world
+        .query_filtered::<&Transform, With<Balloon>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation.truncate())
+        .collect()
+}
+
+pub fn arrow_positions(app: &mut App) -> Vec<Vec2> {
+    app.world
+        .query_filtered::<&Transform, With<Arrow>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation.truncate())
+        .collect()
+}
+
+/// Plays through the levels for at most `ticks` timesteps by shooting straight at a balloon
+/// whenever no arrow is in the air. The first level is failed once on purpose, by shooting
+/// downwards, and then retried.
+pub fn play(app: &mut App, ticks: u64) {
+    let mut failed = false;
+    for _ in 0..ticks {
+        headless::run_ticks(app, 1);
+        match state(app) {
+            GameState::GameOver if !failed => {
+                failed = true;
+                app.world.send_event(RetryLevelEvent);
+                continue;
+            }
+            GameState::Playing => {}
+            _ => continue,
+        }
+
+        let arrows_left = app.world.resource::<Ammo>().arrows;
+        if arrows_left == 0 || !arrow_positions(app).is_empty() {
+            continue;
+        }
+        let Some(target) = balloon_positions(app).first().copied() else {
+            continue;
+        };
+        let velocity = if failed {
+            Vec2::new(900.0, 0.0)
+        } else {
+            Vec2::new(0.0, -900.0)
+        };
+        shoot(app, target - Vec2::new(40.0, 0.0), velocity);
+    }
+}
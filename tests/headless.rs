@@ -0,0 +1,497 @@
+//! Stepping the game headless and checking the score and where things are.
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
+use bloons::ammo::Ammo;
+use bloons::arena::WallBehaviour;
+use bloons::balloons::BalloonKind;
+use bloons::level::{Balloon, CurrentLevel, Level, RetryLevelEvent};
+use bloons::physics::predict_trajectory;
+use bloons::physics::{Arrow, Lifetime, Shot, ShotEnd, ShotFinishedEvent};
+use bloons::scoring::Scoreboard;
+use bloons::{headless, BloonsPlugin, GameState, PhysicsBackend};
+
+use common::{arrow_positions, balloon_positions, shoot, start, start_level, state};
+
+/// One balloon in front of the monkey, without bonus points so that only the pops score.
+const ONE_BALLOON: &str = r#"(
+    arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+    monkey: (-330.0, 0.0),
+    arrows: 3,
+    balloons: [At((0.0, 0.0))],
+    scoring: (arrow_bonus: 0, time_bonus: 0),
+)"#;
+
+#[test]
+fn popping_the_last_balloon_completes_the_level() {
+    let mut app = start_level(ONE_BALLOON);
+    assert_eq!(balloon_positions(&mut app), vec![Vec2::ZERO]);
+
+    shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+
+    assert!(balloon_positions(&mut app).is_empty());
+    assert_eq!(app.world.resource::<Scoreboard>().score, 1);
+    assert_eq!(state(&app), GameState::LevelComplete);
+}
+
+#[test]
+fn popping_the_last_balloon_with_the_last_arrow_completes_the_level() {
+    for physics in [PhysicsBackend::Builtin, PhysicsBackend::Rapier] {
+        let level = Level::from_ron(
+            r#"(
+                arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+                monkey: (-330.0, 0.0),
+                arrows: 1,
+                pierce: Some(1),
+                balloons: [At((0.0, 0.0))],
+            )"#,
+        )
+        .unwrap();
+        let mut app = start(
+            BloonsPlugin::headless()
+                .with_level(level)
+                .with_physics(physics),
+        );
+
+        // The arrow is used up by the balloon in the same timestep as the balloon pops
+        shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+        headless::run_ticks(&mut app, 10);
+
+        assert_eq!(state(&app), GameState::LevelComplete, "{physics:?}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "there has to be at least one level")]
+fn games_without_levels_are_rejected() {
+    BloonsPlugin::headless().with_levels(Vec::new());
+}
+
+#[test]
+fn arrows_follow_the_predicted_path() {
+    let mut app = start_level(ONE_BALLOON);
+    let shot = Shot {
+        position: Vec2::new(-300.0, 100.0),
+        velocity: Vec2::new(300.0, 600.0),
+    };
+    let path: Vec<Vec2> = predict_trajectory(shot, app.world.resource::<FixedTime>())
+        .take(30)
+        .collect();
+
+    shoot(&mut app, shot.position, shot.velocity);
+    // The arrow is spawned during the first timestep and starts moving in the next one
+    headless::run_ticks(&mut app, 1);
+    for expected in path {
+        headless::run_ticks(&mut app, 1);
+        assert_eq!(arrow_positions(&mut app), vec![expected]);
+    }
+    assert_eq!(app.world.resource::<Scoreboard>().score, 0);
+}
+
+#[test]
+fn rapier_colliders_match_the_sprites() {
+    let level = Level::from_ron(ONE_BALLOON).unwrap();
+    let mut app = start(
+        BloonsPlugin::headless()
+            .with_level(level)
+            .with_physics(PhysicsBackend::Rapier),
+    );
+
+    // The balloon is as big as its sprite
+    let context = app.world.resource::<RapierContext>();
+    let ray = |y| {
+        context
+            .cast_ray(Vec2::new(-100.0, y), Vec2::X, 200.0, true, default())
+            .map(|(entity, _)| entity)
+    };
+    assert!(ray(12.0).is_some());
+    assert_eq!(ray(20.0), None);
+
+    // The walls are as long as the sides of the arena, so the arrow sticks in the right one
+    shoot(&mut app, Vec2::new(300.0, 200.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+    assert!(arrow_positions(&mut app).is_empty());
+
+    // Passing 25 pixels above the center of the balloon misses it, since the arrow is thin
+    shoot(&mut app, Vec2::new(-100.0, 25.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+    assert_eq!(balloon_positions(&mut app).len(), 1);
+
+    shoot(&mut app, Vec2::new(-100.0, 10.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+    assert!(balloon_positions(&mut app).is_empty());
+}
+
+#[test]
+fn chains_swing_when_hit() {
+    const LINK_SIZE: Vec2 = Vec2::new(10.0, 40.0);
+
+    fn link_positions(app: &mut App) -> Vec<Vec2> {
+        let mut links: Vec<Vec2> = app
+            .world
+            .query_filtered::<&Transform, With<WallBehaviour>>()
+            .iter(&app.world)
+            .filter(|transform| transform.scale.truncate() == LINK_SIZE)
+            .map(|transform| transform.translation.truncate())
+            .collect();
+        links.sort_by(|a, b| b.y.total_cmp(&a.y));
+        links
+    }
+
+    for physics in [PhysicsBackend::Builtin, PhysicsBackend::Rapier] {
+        let level = Level::from_ron(
+            r#"(
+                arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+                monkey: (-330.0, 0.0),
+                arrows: 3,
+                balloons: [At((300.0, 0.0))],
+                chains: [(anchor: (0.0, 295.0), links: 4, link_size: (10.0, 40.0))],
+            )"#,
+        )
+        .unwrap();
+        let mut app = start(
+            BloonsPlugin::headless()
+                .with_level(level)
+                .with_physics(physics),
+        );
+        let hanging = link_positions(&mut app);
+        assert!(
+            hanging[3].distance(Vec2::new(0.0, 155.0)) < 1.0,
+            "{hanging:?}"
+        );
+
+        // The arrow is stopped by the bottom link, which only swings away with rapier
+        shoot(&mut app, Vec2::new(-100.0, 155.0), Vec2::new(1500.0, 0.0));
+        headless::run_ticks(&mut app, 10);
+        assert!(arrow_positions(&mut app).is_empty(), "{physics:?}");
+
+        let links = link_positions(&mut app);
+        assert_eq!(links.len(), 4, "{physics:?}");
+        match physics {
+            PhysicsBackend::Builtin => assert_eq!(links, hanging),
+            PhysicsBackend::Rapier => {
+                // Pushed to the right, while the links stay joined to each other
+                assert!(links[3].x > 5.0, "{links:?}");
+                assert!(links[0].distance(Vec2::new(0.0, 295.0)) < 21.0, "{links:?}");
+                for pair in links.windows(2) {
+                    assert!(pair[0].distance(pair[1]) < 41.0, "{links:?}");
+                }
+            }
+        }
+    }
+}
+
+/// Two levels of randomly placed balloons. The first one only has room for its balloon in the
+/// middle of the arena, but still draws its position from the random number generator.
+const RANDOM_LEVELS: &str = r#"[
+    (
+        arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+        monkey: (-330.0, 0.0),
+        arrows: 1,
+        balloons: [Random(count: 1, min: (0.0, 0.0), max: (0.0, 0.0))],
+    ),
+    (
+        arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+        monkey: (-330.0, 0.0),
+        arrows: 1,
+        balloons: [Random(count: 5, min: (100.0, -200.0), max: (400.0, 200.0))],
+    ),
+]"#;
+
+fn random_levels() -> Vec<Level> {
+    ron::from_str(RANDOM_LEVELS).unwrap()
+}
+
+fn layout(app: &mut App) -> Vec<Vec2> {
+    let mut positions = balloon_positions(app);
+    positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    positions
+}
+
+/// Wins the first of the [`RANDOM_LEVELS`] and waits for the second one to start.
+fn play_through_first_level(app: &mut App) {
+    shoot(app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+    while app.world.resource::<CurrentLevel>().index == 0 || state(app) != GameState::Playing {
+        app.update();
+    }
+}
+
+/// Misses with the only arrow and waits for the game to be over.
+fn lose(app: &mut App) {
+    shoot(app, Vec2::new(-300.0, 0.0), Vec2::new(0.0, -900.0));
+    while state(app) != GameState::GameOver {
+        app.update();
+    }
+}
+
+#[test]
+fn the_same_seed_gives_the_same_layout() {
+    let layout_for = |seed| layout(&mut start(BloonsPlugin::headless().with_seed(seed)));
+
+    assert_eq!(layout_for(7), layout_for(7));
+    assert_ne!(layout_for(7), layout_for(8));
+}
+
+#[test]
+fn retrying_a_level_keeps_its_layout() {
+    let mut app = start(
+        BloonsPlugin::headless()
+            .with_levels(random_levels())
+            .with_seed(7),
+    );
+    play_through_first_level(&mut app);
+    let first_attempt = layout(&mut app);
+
+    lose(&mut app);
+    app.world.send_event(RetryLevelEvent);
+    while state(&app) != GameState::Playing {
+        app.update();
+    }
+
+    assert_eq!(layout(&mut app), first_attempt);
+}
+
+#[test]
+fn levels_chosen_in_the_menu_have_the_same_layout_as_when_played_through() {
+    let mut app = start(
+        BloonsPlugin::headless()
+            .with_levels(random_levels())
+            .with_seed(7),
+    );
+    play_through_first_level(&mut app);
+    let played_through = layout(&mut app);
+
+    // Choose the second level in the menu, like `select_level` does, before the first one is
+    // set up
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        BloonsPlugin::headless()
+            .with_levels(random_levels())
+            .with_seed(7),
+    ));
+    while !app.world.contains_resource::<CurrentLevel>() {
+        app.update();
+    }
+    app.world.insert_resource(CurrentLevel {
+        index: 1,
+        attempt: 0,
+    });
+    while state(&app) != GameState::Playing {
+        app.update();
+    }
+
+    assert_eq!(layout(&mut app), played_through);
+}
+
+#[derive(Resource, Default)]
+struct FinishedShots(Vec<(Entity, ShotEnd)>);
+
+fn record_finished_shots(
+    mut events: EventReader<ShotFinishedEvent>,
+    mut finished: ResMut<FinishedShots>,
+) {
+    finished
+        .0
+        .extend(events.iter().map(|event| (event.arrow, event.reason)));
+}
+
+#[test]
+fn arrows_finish_once_when_they_expire_while_hitting_something() {
+    for physics in [PhysicsBackend::Builtin, PhysicsBackend::Rapier] {
+        let level = Level::from_ron(
+            r#"(
+                arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+                monkey: (-330.0, 0.0),
+                arrows: 3,
+                pierce: Some(1),
+                balloons: [At((0.0, 0.0))],
+            )"#,
+        )
+        .unwrap();
+        let mut app = start(
+            BloonsPlugin::headless()
+                .with_level(level)
+                .with_physics(physics),
+        );
+        app.init_resource::<FinishedShots>()
+            .add_systems(Last, record_finished_shots);
+
+        // One arrow is spent on the balloon and one sticks in the right wall, both in the
+        // timestep their lifetime runs out
+        shoot(&mut app, Vec2::new(-30.0, 0.0), Vec2::new(1500.0, 0.0));
+        shoot(&mut app, Vec2::new(420.0, 200.0), Vec2::new(1500.0, 0.0));
+        headless::run_ticks(&mut app, 1);
+        for mut lifetime in app
+            .world
+            .query_filtered::<&mut Lifetime, With<Arrow>>()
+            .iter_mut(&mut app.world)
+        {
+            lifetime.0 = 1;
+        }
+        headless::run_ticks(&mut app, 5);
+
+        let finished = &app.world.resource::<FinishedShots>().0;
+        assert_eq!(finished.len(), 2, "{physics:?}");
+        assert_ne!(finished[0].0, finished[1].0, "{physics:?}");
+        assert!(balloon_positions(&mut app).is_empty(), "{physics:?}");
+    }
+}
+
+#[test]
+fn fast_arrows_hit_small_things() {
+    for physics in [PhysicsBackend::Builtin, PhysicsBackend::Rapier] {
+        let level = Level::from_ron(
+            r#"(
+                arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+                monkey: (-330.0, 0.0),
+                arrows: 3,
+                pierce: Some(1),
+                balloons: [At((0.0, 0.0))],
+                obstacles: [(position: (0.0, 150.0), size: (2.0, 100.0), behaviour: Destroy)],
+            )"#,
+        )
+        .unwrap();
+        let mut app = start(
+            BloonsPlugin::headless()
+                .with_level(level)
+                .with_physics(physics),
+        );
+        app.init_resource::<FinishedShots>()
+            .add_systems(Last, record_finished_shots);
+
+        // Both arrows move 50 pixels per timestep, more than the balloon and the obstacle are wide
+        shoot(&mut app, Vec2::new(-300.0, 150.0), Vec2::new(3000.0, 0.0));
+        headless::run_ticks(&mut app, 20);
+        shoot(&mut app, Vec2::new(-300.0, 0.0), Vec2::new(3000.0, 0.0));
+        headless::run_ticks(&mut app, 20);
+
+        let reasons: Vec<ShotEnd> = app
+            .world
+            .resource::<FinishedShots>()
+            .0
+            .iter()
+            .map(|(_, reason)| *reason)
+            .collect();
+        assert_eq!(reasons, [ShotEnd::Destroyed, ShotEnd::Spent], "{physics:?}");
+        assert!(balloon_positions(&mut app).is_empty(), "{physics:?}");
+    }
+}
+
+fn balloon_kinds(app: &mut App) -> Vec<BalloonKind> {
+    app.world
+        .query_filtered::<&BalloonKind, With<Balloon>>()
+        .iter(&app.world)
+        .copied()
+        .collect()
+}
+
+#[test]
+fn layered_balloons_lose_one_layer_per_hit() {
+    let mut app = start_level(
+        r#"(
+            arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+            monkey: (-330.0, 0.0),
+            arrows: 5,
+            pierce: Some(1),
+            balloons: [Special(Layered(layers: 3), (0.0, 0.0))],
+            scoring: (arrow_bonus: 0, time_bonus: 0),
+        )"#,
+    );
+
+    for layers in [2, 1] {
+        shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+        headless::run_ticks(&mut app, 10);
+        assert_eq!(balloon_kinds(&mut app), [BalloonKind::Layered { layers }]);
+    }
+    shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+
+    assert!(balloon_kinds(&mut app).is_empty());
+    // One point per layer
+    assert_eq!(app.world.resource::<Scoreboard>().score, 3);
+    assert_eq!(state(&app), GameState::LevelComplete);
+}
+
+#[test]
+fn explosions_set_off_other_explosive_balloons() {
+    let mut app = start_level(
+        r#"(
+            arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+            monkey: (-330.0, 0.0),
+            arrows: 3,
+            pierce: Some(1),
+            balloons: [
+                Special(Explosive(radius: 60.0), (0.0, 0.0)),
+                // Only in range of the second explosion
+                Special(Explosive(radius: 60.0), (0.0, -50.0)),
+                At((0.0, -100.0)),
+                Special(Metal, (40.0, -80.0)),
+                // Out of range of both
+                At((0.0, 200.0)),
+            ],
+        )"#,
+    );
+
+    shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+
+    assert_eq!(balloon_positions(&mut app), [Vec2::new(0.0, 200.0)]);
+}
+
+#[test]
+fn bonus_balloons_give_more_arrows() {
+    let mut app = start_level(
+        r#"(
+            arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+            monkey: (-330.0, 0.0),
+            arrows: 3,
+            pierce: Some(1),
+            balloons: [Special(Bonus(arrows: 2), (0.0, 0.0)), At((0.0, 200.0))],
+        )"#,
+    );
+
+    shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+    headless::run_ticks(&mut app, 10);
+
+    assert_eq!(balloon_positions(&mut app), [Vec2::new(0.0, 200.0)]);
+    assert_eq!(app.world.resource::<Ammo>().arrows, 4);
+}
+
+#[test]
+fn arrows_pop_as_many_balloons_as_their_pierce() {
+    for physics in [PhysicsBackend::Builtin, PhysicsBackend::Rapier] {
+        let level = Level::from_ron(
+            r#"(
+                arena: (left: -450.0, right: 450.0, bottom: -300.0, top: 300.0),
+                monkey: (-330.0, 0.0),
+                arrows: 3,
+                pierce: Some(3),
+                balloons: [
+                    At((0.0, 0.0)),
+                    At((60.0, 0.0)),
+                    At((120.0, 0.0)),
+                    At((180.0, 0.0)),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let mut app = start(
+            BloonsPlugin::headless()
+                .with_level(level)
+                .with_physics(physics),
+        );
+
+        shoot(&mut app, Vec2::new(-100.0, 0.0), Vec2::new(1500.0, 0.0));
+        headless::run_ticks(&mut app, 40);
+
+        assert_eq!(
+            balloon_positions(&mut app),
+            [Vec2::new(180.0, 0.0)],
+            "{physics:?}"
+        );
+    }
+}
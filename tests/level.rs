@@ -0,0 +1,58 @@
+//! Reading levels from RON.
+
+use bloons::level::{Level, LevelError};
+
+const ARENA: &str = "(left: -450.0, right: 450.0, bottom: -300.0, top: 300.0)";
+
+#[test]
+fn builtin_levels_are_valid() {
+    assert_eq!(Level::builtin().len(), bloons::level::LEVEL_PATHS.len());
+}
+
+#[test]
+fn builtin_levels_have_distinct_names() {
+    // The records are kept by name
+    let mut names: Vec<String> = Level::builtin()
+        .into_iter()
+        .map(|level| level.name)
+        .collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), bloons::level::LEVEL_PATHS.len());
+    assert!(names.iter().all(|name| !name.is_empty()));
+}
+
+#[test]
+fn arenas_without_area_are_rejected() {
+    for arena in [
+        "(left: 450.0, right: -450.0, bottom: -300.0, top: 300.0)",
+        "(left: -450.0, right: 450.0, bottom: 300.0, top: 300.0)",
+    ] {
+        let ron = format!("(arena: {arena}, monkey: (0.0, 0.0), arrows: 1, balloons: [])");
+        assert!(matches!(
+            Level::from_ron(&ron),
+            Err(LevelError::InvalidArena(_))
+        ));
+    }
+}
+
+#[test]
+fn levels_without_arrows_are_rejected() {
+    let ron = format!("(arena: {ARENA}, monkey: (0.0, 0.0), arrows: 0, balloons: [])");
+    assert!(matches!(Level::from_ron(&ron), Err(LevelError::NoArrows)));
+}
+
+#[test]
+fn arrows_that_cant_pop_anything_are_rejected() {
+    let ron =
+        format!("(arena: {ARENA}, monkey: (0.0, 0.0), arrows: 1, pierce: Some(0), balloons: [])");
+    assert!(matches!(Level::from_ron(&ron), Err(LevelError::NoPierce)));
+}
+
+#[test]
+fn syntax_errors_are_reported() {
+    assert!(matches!(
+        Level::from_ron("(arena: "),
+        Err(LevelError::Ron(_))
+    ));
+}
@@ -0,0 +1,106 @@
+//! Where balloons moving along paths are at a given time.
+
+use bevy::prelude::*;
+use bloons::paths::BalloonPath;
+
+fn assert_close(actual: Vec2, expected: Vec2) {
+    assert!(
+        actual.distance(expected) < 1e-3,
+        "{actual:?} is not close to {expected:?}"
+    );
+}
+
+#[test]
+fn lines_go_back_and_forth() {
+    let path = BalloonPath::Line {
+        offset: Vec2::new(0.0, 80.0),
+        period: 4.0,
+    };
+    assert_close(path.offset(0.0), Vec2::ZERO);
+    assert_close(path.offset(1.0), Vec2::new(0.0, 40.0));
+    assert_close(path.offset(2.0), Vec2::new(0.0, 80.0));
+    assert_close(path.offset(3.0), Vec2::new(0.0, 40.0));
+    assert_close(path.offset(4.0), Vec2::ZERO);
+}
+
+#[test]
+fn circles_start_on_the_right() {
+    let path = BalloonPath::Circle {
+        radius: 50.0,
+        period: 4.0,
+    };
+    assert_close(path.offset(0.0), Vec2::new(50.0, 0.0));
+    assert_close(path.offset(1.0), Vec2::new(0.0, 50.0));
+    assert_close(path.offset(2.0), Vec2::new(-50.0, 0.0));
+
+    let clockwise = BalloonPath::Circle {
+        radius: 50.0,
+        period: -4.0,
+    };
+    assert_close(clockwise.offset(1.0), Vec2::new(0.0, -50.0));
+}
+
+#[test]
+fn bobbing_goes_up_first() {
+    let path = BalloonPath::Bob {
+        height: 20.0,
+        period: 2.0,
+    };
+    assert_close(path.offset(0.0), Vec2::ZERO);
+    assert_close(path.offset(0.5), Vec2::new(0.0, 20.0));
+    assert_close(path.offset(1.5), Vec2::new(0.0, -20.0));
+}
+
+#[test]
+fn splines_pass_through_their_points_and_loop_back() {
+    let points = vec![
+        Vec2::new(100.0, 60.0),
+        Vec2::new(200.0, 0.0),
+        Vec2::new(100.0, -60.0),
+    ];
+    let path = BalloonPath::Spline {
+        points: points.clone(),
+        period: 8.0,
+    };
+
+    // The place of the balloon is the first of four points, each reached after a quarter period
+    assert_close(path.offset(0.0), Vec2::ZERO);
+    for (i, point) in points.into_iter().enumerate() {
+        assert_close(path.offset(2.0 * (i + 1) as f32), point);
+    }
+
+    // Wrapping around from the last point to the start is smooth
+    assert_close(path.offset(8.0), Vec2::ZERO);
+    let before = path.offset(7.99);
+    let after = path.offset(8.01);
+    assert!(before.distance(Vec2::ZERO) < 1.0, "{before:?}");
+    assert!(after.distance(Vec2::ZERO) < 1.0, "{after:?}");
+}
+
+#[test]
+fn paths_repeat_every_period() {
+    let paths = [
+        BalloonPath::Line {
+            offset: Vec2::new(30.0, 80.0),
+            period: 3.0,
+        },
+        BalloonPath::Circle {
+            radius: 50.0,
+            period: -3.0,
+        },
+        BalloonPath::Spline {
+            points: vec![Vec2::new(100.0, 60.0), Vec2::new(200.0, 0.0)],
+            period: 3.0,
+        },
+        BalloonPath::Bob {
+            height: 20.0,
+            period: 3.0,
+        },
+    ];
+    for path in paths {
+        for time in [0.0, 0.4, 1.3, 2.9] {
+            assert_close(path.offset(time + 3.0), path.offset(time));
+            assert_close(path.offset(time + 30.0), path.offset(time));
+        }
+    }
+}
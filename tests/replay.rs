@@ -0,0 +1,80 @@
+//! Playing back a recorded game gives exactly the same game.
+
+mod common;
+
+use bevy::prelude::*;
+use bloons::scoring::Scoreboard;
+use bloons::{headless, BloonsPlugin, GameState, PhysicsBackend, Replay};
+
+use common::{play, shoot, start, state};
+
+/// Enough for the first levels, including failing and retrying the first one.
+const TICKS: u64 = 3000;
+/// Enough for every arrow still in the air to land.
+const SETTLE_TICKS: u64 = 600;
+
+fn replays_identically(physics: PhysicsBackend) {
+    let mut recording = start(BloonsPlugin::headless().with_seed(42).with_physics(physics));
+    play(&mut recording, TICKS);
+    headless::run_ticks(&mut recording, SETTLE_TICKS);
+    let replay = recording.world.resource::<Replay>().clone();
+    assert!(
+        replay.shots.iter().any(|shot| shot.attempt > 0),
+        "the game should include a retry"
+    );
+
+    let mut playback = start(BloonsPlugin::headless().with_replay(replay.clone()));
+    headless::run_ticks(&mut playback, TICKS + SETTLE_TICKS);
+
+    assert_eq!(*playback.world.resource::<Replay>(), replay);
+    let recorded = recording.world.resource::<Scoreboard>();
+    let played_back = playback.world.resource::<Scoreboard>();
+    assert!(recorded.score > 0);
+    assert_eq!(played_back.score, recorded.score);
+    assert_eq!(played_back.level_score, recorded.level_score);
+}
+
+#[test]
+fn builtin_physics_replays_identically() {
+    replays_identically(PhysicsBackend::Builtin);
+}
+
+#[test]
+fn rapier_physics_replays_identically() {
+    replays_identically(PhysicsBackend::Rapier);
+}
+
+#[test]
+fn only_the_first_game_is_recorded() {
+    let mut app = start(BloonsPlugin::headless().with_seed(42));
+    shoot(&mut app, Vec2::new(0.0, 0.0), Vec2::new(0.0, -900.0));
+    headless::run_ticks(&mut app, 1);
+
+    // Going back to the main menu starts a new game right away when headless
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::MainMenu);
+    app.update();
+    assert_eq!(state(&app), GameState::MainMenu);
+    while state(&app) != GameState::Playing {
+        app.update();
+    }
+    shoot(&mut app, Vec2::new(0.0, 0.0), Vec2::new(0.0, 900.0));
+    headless::run_ticks(&mut app, 2);
+
+    let replay = app.world.resource::<Replay>();
+    assert_eq!(replay.shots.len(), 1);
+    assert_eq!(replay.shots[0].velocity, Vec2::new(0.0, -900.0));
+}
+
+#[test]
+#[should_panic(expected = "the replay starts from level 4, but there are only 3 levels")]
+fn replays_starting_past_the_last_level_are_rejected() {
+    let replay = Replay {
+        seed: 42,
+        physics: PhysicsBackend::Builtin,
+        start_level: 3,
+        shots: Vec::new(),
+    };
+    App::new().add_plugins((MinimalPlugins, BloonsPlugin::headless().with_replay(replay)));
+}
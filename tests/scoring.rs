@@ -0,0 +1,47 @@
+//! The points given by the scoring rules of a level.
+
+use bloons::scoring::ScoringRules;
+
+#[test]
+fn combos_multiply_the_points_up_to_a_limit() {
+    let rules = ScoringRules {
+        combo_step: 0.5,
+        max_multiplier: 2.0,
+        ..Default::default()
+    };
+    assert_eq!(rules.combo_points(3, 1), 3);
+    assert_eq!(rules.combo_points(3, 2), 5);
+    assert_eq!(rules.combo_points(3, 3), 6);
+    assert_eq!(rules.combo_points(3, 10), 6);
+}
+
+#[test]
+fn the_first_pop_of_an_arrow_gets_the_normal_points() {
+    let rules = ScoringRules::default();
+    assert_eq!(rules.combo_points(2, 0), 2);
+    assert_eq!(rules.combo_points(2, 1), 2);
+}
+
+#[test]
+fn the_time_bonus_shrinks_until_the_time_limit() {
+    let rules = ScoringRules {
+        time_bonus: 20,
+        time_limit: 60.0,
+        ..Default::default()
+    };
+    assert_eq!(rules.time_points(0.0), 20);
+    assert_eq!(rules.time_points(30.0), 10);
+    assert_eq!(rules.time_points(45.0), 5);
+    assert_eq!(rules.time_points(60.0), 0);
+    assert_eq!(rules.time_points(90.0), 0);
+}
+
+#[test]
+fn there_is_no_time_bonus_without_a_time_limit() {
+    let rules = ScoringRules {
+        time_bonus: 20,
+        time_limit: 0.0,
+        ..Default::default()
+    };
+    assert_eq!(rules.time_points(0.0), 0);
+}